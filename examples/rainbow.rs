@@ -1,4 +1,5 @@
-use dyngpu::{Device, Error, RenderApp, Resources, Task};
+use dyngpu::buffer::TypedBuffer;
+use dyngpu::{Device, Error, RenderApp, RenderContext, Resources, Task, UpdateContext};
 
 fn main() -> Result<(), Error> {
     RenderApp::default()
@@ -7,11 +8,10 @@ fn main() -> Result<(), Error> {
 }
 
 pub struct RenderRainbow {
-    vertex_buffer: wgpu::Buffer,
+    vertex_buffer: TypedBuffer<[f32; 3]>,
     render_pipeline: wgpu::RenderPipeline,
-    update: u32,
-    update_uniform: wgpu::Buffer,
-    update_bind_group: wgpu::BindGroup,
+    time_uniform: TypedBuffer<f32>,
+    time_bind_group: wgpu::BindGroup,
 }
 
 impl Task for RenderRainbow {
@@ -19,21 +19,20 @@ impl Task for RenderRainbow {
     where
         Self: Sized,
     {
-        let update = 0;
-        let update_uniform = device
+        let time_uniform = device
             .build_buffer()
-            .contents_slice(&[update])
+            .contents_slice(&[0f32])
             .uniform()
             .copy_dst()
-            .finish();
-        let (update_bind_group, layout) = device
+            .finish_typed();
+        let (time_bind_group, layout) = device
             .build_bind_group()
-            .uniform(&update_uniform, wgpu::ShaderStages::FRAGMENT)
+            .uniform(&time_uniform, wgpu::ShaderStages::FRAGMENT)
             .finish_with_layout();
 
         let shader = res.create_shader("rainbow", wgpu::include_wgsl!("rainbow.wgsl"));
         let render_pipeline = device
-            .build_pipeline(&shader)
+            .build_pipeline(&shader, res.surface_target())
             .vert_buffer(wgpu::VertexBufferLayout {
                 array_stride: size_of::<[f32; 3]>() as wgpu::BufferAddress,
                 step_mode: wgpu::VertexStepMode::Vertex,
@@ -47,27 +46,24 @@ impl Task for RenderRainbow {
             .build_buffer()
             .contents_slice(&vertices)
             .vert()
-            .finish();
+            .finish_typed();
 
         Self {
             vertex_buffer,
             render_pipeline,
-            update,
-            update_uniform,
-            update_bind_group,
+            time_uniform,
+            time_bind_group,
         }
     }
 
-    fn update(&mut self, device: &Device) {
-        self.update += 1;
-        device.queue()
-            .write_buffer(&self.update_uniform, 0, bytemuck::cast_slice(&[self.update]));
+    fn update(&mut self, _: &Device, ctx: &mut UpdateContext) {
+        self.time_uniform.write(0, &[ctx.elapsed_secs()]);
     }
 
-    fn render(&self, _: &Device, render_pass: &mut wgpu::RenderPass) {
+    fn render(&mut self, _: &Device, _: &RenderContext, render_pass: &mut wgpu::RenderPass) {
         render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_bind_group(0, &self.update_bind_group, &[]);
+        render_pass.set_bind_group(0, &self.time_bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        render_pass.draw(0..6, 0..1);
+        render_pass.draw(0..self.vertex_buffer.len() as u32, 0..1);
     }
 }
\ No newline at end of file
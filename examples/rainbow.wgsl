@@ -1,4 +1,4 @@
-@group(0) @binding(0) var<uniform> update: u32;
+@group(0) @binding(0) var<uniform> time: f32;
 
 struct Fragment {
     @builtin(position) position: vec4f,
@@ -14,10 +14,10 @@ fn vs(@location(0) position: vec3f) -> Fragment {
 
 @fragment
 fn fs(frag: Fragment) -> @location(0) vec4f {
-    let frequency = 3.14159 / 40.0;
-    let r = sin((frequency * f32(update)) + 0.0) * 0.5 + 0.5;
-    let g = sin((frequency * f32(update)) + 2.0) * 0.5 + 0.5;
-    let b = sin((frequency * f32(update)) + 4.0) * 0.5 + 0.5;
+    let frequency = 3.14159 * 1.5;
+    let r = sin((frequency * time) + 0.0) * 0.5 + 0.5;
+    let g = sin((frequency * time) + 2.0) * 0.5 + 0.5;
+    let b = sin((frequency * time) + 4.0) * 0.5 + 0.5;
 
     return vec4f(r, g, b, 1);
 }
@@ -1,4 +1,4 @@
-use dyngpu::{Device, Error, RenderApp, Resources, Task};
+use dyngpu::{Device, Error, RenderApp, RenderContext, Resources, Task, UpdateContext};
 
 pub fn main() -> Result<(), Error> {
     RenderApp::stateless()
@@ -24,7 +24,7 @@ impl Task for RenderTriangle {
         let vertex_buffer = device.build_buffer().contents_slice(&vertices).vert().finish();
 
         let shader = res.create_shader("triangle", wgpu::include_wgsl!("triangle.wgsl"));
-        let render_pipeline = device.build_pipeline(&shader)
+        let render_pipeline = device.build_pipeline(&shader, res.surface_target())
             .vert_buffer(VERTEX_BUFFER_LAYOUT)
             .finish();
 
@@ -35,9 +35,9 @@ impl Task for RenderTriangle {
         }
     }
 
-    fn update(&mut self, _: &Device) {}
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {}
 
-    fn render(&self, _: &Device, render_pass: &mut wgpu::RenderPass) {
+    fn render(&mut self, _: &Device, _: &RenderContext, render_pass: &mut wgpu::RenderPass) {
         render_pass.set_pipeline(&self.render_pipeline);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.draw(0..3, 0..1);
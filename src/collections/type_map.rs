@@ -64,7 +64,7 @@ impl TypeMap {
     }
 
     pub fn get<T: 'static>(&self) -> Option<&T> {
-        self.get_by_id(TypeId::of::<T>()).map(|x| x.downcast_ref::<T>()).flatten()
+        self.get_by_id(TypeId::of::<T>()).and_then(|x| x.downcast_ref::<T>())
     }
 
     pub fn get_by_id(&self, id: TypeId) -> Option<&Box<dyn Any>> {
@@ -72,7 +72,7 @@ impl TypeMap {
     }
 
     pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
-        self.get_mut_by_id(TypeId::of::<T>()).map(|x| x.downcast_mut::<T>()).flatten()
+        self.get_mut_by_id(TypeId::of::<T>()).and_then(|x| x.downcast_mut::<T>())
     }
 
     pub fn get_mut_by_id(&mut self, id: TypeId) -> Option<&mut Box<dyn Any>> {
@@ -6,6 +6,53 @@ pub enum Error {
     RequestAdapter,
     #[error(transparent)]
     RequestDevice(#[from] wgpu::RequestDeviceError),
+    #[error("out of memory while acquiring a surface texture")]
+    OutOfMemory,
+    #[error("no surface texture was available for this frame")]
+    FrameUnavailable,
+    #[error("format {0:?} does not support a sample count of {1}")]
+    UnsupportedSampleCount(wgpu::TextureFormat, u32),
+    #[error("render graph contains a cycle involving node `{0}`")]
+    GraphCycle(String),
+    #[error("node `{0}` renders to a buffer")]
+    NotATexture(String),
+    #[error("renderer is not initialized")]
+    Uninitialized,
+    #[error("render target does not support being copied from")]
+    CaptureUnsupported,
+    #[error("cannot capture render target of format {0:?}")]
+    UnsupportedCaptureFormat(wgpu::TextureFormat),
+    #[error("failed to construct task `{task}`: {source}")]
+    TaskConstruction {
+        task: &'static str,
+        #[source]
+        source: crate::TaskError,
+    },
+    #[error("task ordering contains a cycle involving `{0}`")]
+    TaskCycle(String),
+    #[error("buffers cannot be empty")]
+    EmptyBuffer,
+    #[error("buffer size {0} exceeds the device limit of {1} bytes")]
+    BufferTooLarge(u64, u64),
+    #[error("size {0} is not a multiple of {1}")]
+    UnalignedBufferSize(u64, u64),
+    #[error("range {0:?} is out of bounds of a buffer of {1} bytes")]
+    BufferOutOfRange(std::ops::Range<u64>, u64),
+    #[error("dynamic buffers cannot be initialized while mapped")]
+    MappedDynamicBuffer,
+    #[error("invalid buffer usage {0:?}")]
+    InvalidBufferUsage(wgpu::BufferUsages),
+    #[error(transparent)]
+    BufferAsync(#[from] wgpu::BufferAsyncError),
+    #[cfg(feature = "testing")]
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "testing")]
+    #[error(transparent)]
+    PngDecoding(#[from] png::DecodingError),
+    #[cfg(feature = "testing")]
+    #[error(transparent)]
+    PngEncoding(#[from] png::EncodingError),
     #[cfg(feature = "winit")]
     #[error(transparent)]
     Os(#[from] winit::error::OsError),
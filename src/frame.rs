@@ -0,0 +1,290 @@
+use crate::{Resources, TypeMap};
+use std::time::{Duration, Instant};
+
+/// The most updates a fixed timestep runs in one frame, so a long stall does not make every
+/// following frame slower to catch up.
+pub const MAX_FIXED_UPDATES: u32 = 8;
+
+/// Timing information for the frame being drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameContext {
+    delta: Duration,
+    elapsed: Duration,
+    frame: u64,
+    resolution: (u32, u32),
+    fixed: Option<FixedUpdates>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FixedUpdates {
+    step: Duration,
+    start: Duration,
+    count: u32,
+}
+
+impl FrameContext {
+    /// The time since the previous frame, or the fixed timestep during fixed updates.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// The time since the first frame.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    /// The index of this frame, starting at zero.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn resolution(&self) -> (u32, u32) {
+        self.resolution
+    }
+
+    /// The contexts [`Task::update`](crate::Task::update) is called with this frame: this context
+    /// itself, or one per fixed timestep that elapsed since the previous frame.
+    pub fn updates(&self) -> impl Iterator<Item = FrameContext> + use<> {
+        let this = *self;
+        let (count, fixed) = match self.fixed {
+            Some(fixed) => (fixed.count, Some(fixed)),
+            None => (1, None),
+        };
+        (0..count).map(move |i| match fixed {
+            Some(fixed) => FrameContext {
+                delta: fixed.step,
+                elapsed: fixed.start + fixed.step * (i + 1),
+                fixed: None,
+                ..this
+            },
+            None => this,
+        })
+    }
+}
+
+/// The state a task can access while updating. Dereferences to the frame's timing.
+pub struct UpdateContext<'a> {
+    frame: FrameContext,
+    res: &'a mut Resources,
+    scratch: &'a mut TypeMap,
+}
+
+impl<'a> UpdateContext<'a> {
+    pub(crate) fn new(frame: FrameContext, res: &'a mut Resources, scratch: &'a mut TypeMap) -> Self {
+        Self { frame, res, scratch }
+    }
+
+    pub fn res(&self) -> &Resources {
+        self.res
+    }
+
+    pub fn res_mut(&mut self) -> &mut Resources {
+        self.res
+    }
+
+    /// Values shared between the tasks of every stage for the current frame, such as a camera
+    /// matrix written by one task's update and read by another task's render. It is cleared at
+    /// the start of each frame that runs updates.
+    pub fn scratch(&self) -> &TypeMap {
+        self.scratch
+    }
+
+    pub fn scratch_mut(&mut self) -> &mut TypeMap {
+        self.scratch
+    }
+}
+
+impl std::ops::Deref for UpdateContext<'_> {
+    type Target = FrameContext;
+
+    fn deref(&self) -> &Self::Target {
+        &self.frame
+    }
+}
+
+/// The state a task can read while recording commands. Dereferences to the frame's timing.
+pub struct RenderContext<'a> {
+    frame: &'a FrameContext,
+    res: &'a Resources,
+    scratch: &'a TypeMap,
+}
+
+impl<'a> RenderContext<'a> {
+    pub(crate) fn new(frame: &'a FrameContext, res: &'a Resources, scratch: &'a TypeMap) -> Self {
+        Self { frame, res, scratch }
+    }
+
+    pub fn res(&self) -> &'a Resources {
+        self.res
+    }
+
+    /// The values tasks stored with [`UpdateContext::scratch_mut`] this frame.
+    pub fn scratch(&self) -> &'a TypeMap {
+        self.scratch
+    }
+}
+
+impl std::ops::Deref for RenderContext<'_> {
+    type Target = FrameContext;
+
+    fn deref(&self) -> &Self::Target {
+        self.frame
+    }
+}
+
+/// Produces the [`FrameContext`] of each frame a [`Renderer`](crate::Renderer) draws.
+///
+/// Time is measured with the system clock unless a virtual timestep is set, in which case every
+/// frame advances time by exactly that step so rendering is reproducible.
+#[derive(Debug, Default)]
+pub struct Clock {
+    virtual_timestep: Option<Duration>,
+    last: Option<Instant>,
+    elapsed: Duration,
+    frame: u64,
+    fixed_timestep: Option<Duration>,
+    accumulated: Duration,
+    simulated: Duration,
+}
+
+impl Clock {
+    /// Makes tasks update in steps of exactly `step`, as many times per frame as steps have
+    /// elapsed, or once per frame with the measured delta when `None`.
+    pub fn set_fixed_timestep(&mut self, step: Option<Duration>) {
+        self.fixed_timestep = step.filter(|step| !step.is_zero());
+        self.accumulated = Duration::ZERO;
+        self.simulated = self.elapsed;
+    }
+
+    pub fn fixed_timestep(&self) -> Option<Duration> {
+        self.fixed_timestep
+    }
+
+    /// Makes every frame advance time by exactly `step` regardless of how long it took, or
+    /// measures time with the system clock when `None`.
+    pub fn set_virtual_timestep(&mut self, step: Option<Duration>) {
+        self.virtual_timestep = step;
+    }
+
+    pub fn virtual_timestep(&self) -> Option<Duration> {
+        self.virtual_timestep
+    }
+
+    /// The number of frames ticked so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Advances to the next frame. The first frame has a delta of zero.
+    pub fn tick(&mut self, resolution: (u32, u32)) -> FrameContext {
+        let now = Instant::now();
+        let measured = self.last.map_or(Duration::ZERO, |last| now - last);
+        self.last = Some(now);
+        self.advance(measured, resolution)
+    }
+
+    /// Advances to the next frame as if `measured` had passed since the previous one.
+    fn advance(&mut self, measured: Duration, resolution: (u32, u32)) -> FrameContext {
+        let delta = match self.virtual_timestep {
+            _ if self.frame == 0 => Duration::ZERO,
+            Some(step) => step,
+            None => measured,
+        };
+        self.elapsed += delta;
+
+        let fixed = self.fixed_timestep.map(|step| {
+            self.accumulated += delta;
+            let count = (self.accumulated.as_nanos() / step.as_nanos()).min(MAX_FIXED_UPDATES as u128) as u32;
+            self.accumulated = self.accumulated.saturating_sub(step * count).min(step);
+            let start = self.simulated;
+            self.simulated += step * count;
+            FixedUpdates { step, start, count }
+        });
+
+        let context = FrameContext {
+            delta,
+            elapsed: self.elapsed,
+            frame: self.frame,
+            resolution,
+            fixed,
+        };
+        self.frame += 1;
+        context
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: Duration = Duration::from_millis(10);
+
+    fn fixed_updates(clock: &mut Clock, measured: Duration) -> Vec<Duration> {
+        clock.advance(measured, (1, 1)).updates().map(|u| u.elapsed()).collect()
+    }
+
+    #[test]
+    fn fixed_timestep_runs_one_update_per_elapsed_step() {
+        let mut clock = Clock::default();
+        clock.set_fixed_timestep(Some(STEP));
+        assert_eq!(fixed_updates(&mut clock, Duration::ZERO), []);
+        assert_eq!(fixed_updates(&mut clock, STEP * 2), [STEP, STEP * 2]);
+    }
+
+    #[test]
+    fn fixed_timestep_carries_the_remainder() {
+        let mut clock = Clock::default();
+        clock.set_fixed_timestep(Some(STEP));
+        clock.advance(Duration::ZERO, (1, 1));
+        assert_eq!(fixed_updates(&mut clock, Duration::from_millis(15)), [STEP]);
+        assert_eq!(fixed_updates(&mut clock, Duration::from_millis(5)), [STEP * 2]);
+        assert_eq!(fixed_updates(&mut clock, Duration::from_millis(9)), []);
+    }
+
+    #[test]
+    fn fixed_timestep_clamps_updates_after_a_stall() {
+        let mut clock = Clock::default();
+        clock.set_fixed_timestep(Some(STEP));
+        clock.advance(Duration::ZERO, (1, 1));
+        let updates = fixed_updates(&mut clock, STEP * 100);
+        assert_eq!(updates.len(), MAX_FIXED_UPDATES as usize);
+        assert_eq!(updates.last(), Some(&(STEP * MAX_FIXED_UPDATES)));
+
+        // Only up to one step of the stall is carried into the next frame.
+        assert_eq!(fixed_updates(&mut clock, Duration::ZERO).len(), 1);
+        assert_eq!(fixed_updates(&mut clock, Duration::ZERO).len(), 0);
+    }
+
+    #[test]
+    fn virtual_timestep_ignores_measured_time() {
+        let mut clock = Clock::default();
+        clock.set_virtual_timestep(Some(STEP));
+        for _ in 0..30 {
+            let frame = clock.advance(Duration::from_secs(1), (1, 1));
+            assert_eq!(frame.elapsed(), STEP * frame.frame() as u32);
+        }
+        assert_eq!(clock.frame(), 30);
+    }
+
+    #[test]
+    fn variable_timestep_updates_once_with_the_measured_delta() {
+        let mut clock = Clock::default();
+        clock.advance(Duration::ZERO, (1, 1));
+        let frame = clock.advance(Duration::from_millis(7), (1, 1));
+        let updates: Vec<_> = frame.updates().collect();
+        assert_eq!(updates, [frame]);
+        assert_eq!(frame.delta(), Duration::from_millis(7));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{Device, Error, Surface};
+
+/// Identifies a texture managed by a [`Surface`] that always matches its resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AttachmentId(usize);
+
+#[derive(Debug)]
+pub(crate) struct Attachment {
+    format: wgpu::TextureFormat,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Attachment {
+    pub(crate) fn new(
+        device: &Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let texture = device.device().create_texture(&wgpu::TextureDescriptor {
+            label: Some("surface attachment"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { format, texture, view }
+    }
+
+    pub(crate) fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+}
+
+impl Surface<'_> {
+    /// Creates a texture that is recreated whenever the surface is resized or its sample count
+    /// changes.
+    pub fn create_attachment(&mut self, format: wgpu::TextureFormat) -> Result<AttachmentId, Error> {
+        if !self.device().supports_sample_count(format, self.sample_count()) {
+            return Err(Error::UnsupportedSampleCount(format, self.sample_count()));
+        }
+
+        let attachment = Attachment::new(self.device(), format, self.sample_count(), self.width(), self.height());
+        self.attachments.push(attachment);
+        Ok(AttachmentId(self.attachments.len() - 1))
+    }
+
+    pub fn attachment_texture(&self, id: AttachmentId) -> &wgpu::Texture {
+        &self.attachments[id.0].texture
+    }
+
+    pub fn attachment_view(&self, id: AttachmentId) -> &wgpu::TextureView {
+        &self.attachments[id.0].view
+    }
+
+    /// The multisampled texture stages render to before resolving into the surface texture, if
+    /// the sample count is greater than one.
+    pub fn multisample_view(&self) -> Option<&wgpu::TextureView> {
+        self.multisample.as_ref().map(|a| &a.view)
+    }
+
+    pub(crate) fn resize_attachments(&mut self) {
+        let (width, height, sample_count) = (self.width(), self.height(), self.sample_count());
+        let device = self.device().clone();
+        for attachment in &mut self.attachments {
+            *attachment = Attachment::new(&device, attachment.format, sample_count, width, height);
+        }
+        self.multisample = (sample_count > 1)
+            .then(|| Attachment::new(&device, self.format(), sample_count, width, height));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{Device, Error};
+
+/// An RGBA8 image read back from a render target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Self {
+        assert_eq!(data.len(), width as usize * height as usize * 4, "image data does not match its size");
+        Self { width, height, data }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+    }
+}
+
+/// A texture copy that has been recorded into an encoder but not yet read back.
+pub struct PendingCapture {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    swizzle: bool,
+}
+
+impl PendingCapture {
+    pub fn new(device: &Device, encoder: &mut wgpu::CommandEncoder, texture: &wgpu::Texture) -> Result<Self, Error> {
+        let swizzle = match texture.format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => return Err(Error::UnsupportedCaptureFormat(format)),
+        };
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err(Error::CaptureUnsupported);
+        }
+
+        let (width, height) = (texture.width(), texture.height());
+        let padded_bytes_per_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some("capture"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        Ok(Self { buffer, width, height, padded_bytes_per_row, swizzle })
+    }
+
+    /// Waits for the copy to complete and returns the tightly packed RGBA8 image. The encoder the
+    /// copy was recorded into must have been submitted beforehand.
+    pub fn finish(self, device: &Device) -> Result<Image, Error> {
+        let slice = self.buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| { let _ = sender.send(result); });
+        device.device().poll(wgpu::Maintain::Wait);
+        receiver.recv().map_err(|_| Error::BufferAsync(wgpu::BufferAsyncError))??;
+
+        let row_len = self.width as usize * 4;
+        let mut data = Vec::with_capacity(row_len * self.height as usize);
+        for row in slice.get_mapped_range().chunks(self.padded_bytes_per_row as usize) {
+            data.extend_from_slice(&row[..row_len]);
+        }
+        self.buffer.unmap();
+
+        if self.swizzle {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        Ok(Image::new(self.width, self.height, data))
+    }
+}
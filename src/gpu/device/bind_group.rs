@@ -14,8 +14,16 @@ impl<'a> BindGroupBuilder<'a> {
         self
     }
 
-    pub fn uniform(mut self, buffer: &'a Buffer, visibility: ShaderStages) -> Self {
-        let binding = self.bind_group_layout_entries.len() as u32;
+    pub fn uniform(self, buffer: &'a Buffer, visibility: ShaderStages) -> Self {
+        self.buffer(buffer, visibility, BufferBindingType::Uniform)
+    }
+
+    pub fn storage(self, buffer: &'a Buffer, visibility: ShaderStages, read_only: bool) -> Self {
+        self.buffer(buffer, visibility, BufferBindingType::Storage { read_only })
+    }
+
+    pub fn buffer(mut self, buffer: &'a Buffer, visibility: ShaderStages, ty: BufferBindingType) -> Self {
+        let binding = self.bind_group_entries.len() as u32;
         self.bind_group_entries.push(BindGroupEntry {
             binding,
             resource: buffer.as_entire_binding(),
@@ -25,7 +33,7 @@ impl<'a> BindGroupBuilder<'a> {
                 binding,
                 visibility,
                 ty: BindingType::Buffer {
-                    ty: BufferBindingType::Uniform,
+                    ty,
                     has_dynamic_offset: false,
                     min_binding_size: None,
                 },
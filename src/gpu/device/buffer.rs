@@ -1,17 +1,28 @@
-use crate::Device;
-use bytemuck::NoUninit;
+use crate::{Device, Error};
+use bytemuck::{NoUninit, Pod};
+use std::marker::PhantomData;
+use std::ops::{Bound, Range, RangeBounds};
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
-use wgpu::{Buffer, BufferDescriptor, BufferUsages};
+use wgpu::{Buffer, BufferAddress, BufferDescriptor, BufferSlice, BufferUsages, IndexFormat};
+
+type InitFn<'a> = dyn FnOnce(&mut [u8]) + 'a;
 
 pub struct BufferBuilder<'a> {
     device: Device,
     label: Option<&'a str>,
     contents: Option<&'a [u8]>,
+    init: Option<Box<InitFn<'a>>>,
     size: u64,
     usage: BufferUsages,
+    index_format: Option<IndexFormat>,
 }
 
 impl<'a> BufferBuilder<'a> {
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
     pub fn size(mut self, size: u64) -> Self {
         self.size = size;
         self
@@ -19,6 +30,7 @@ impl<'a> BufferBuilder<'a> {
 
     pub fn contents(mut self, contents: &'a [u8]) -> Self {
         self.contents = Some(contents);
+        self.init = None;
         self
     }
 
@@ -26,37 +38,195 @@ impl<'a> BufferBuilder<'a> {
         self.contents(bytemuck::cast_slice(contents))
     }
 
+    /// Fills the buffer while it is mapped at creation, then unmaps it. The size must be a
+    /// multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`].
+    pub fn mapped_init(mut self, init: impl FnOnce(&mut [u8]) + 'a) -> Self {
+        self.init = Some(Box::new(init));
+        self.contents = None;
+        self
+    }
+
+    /// Finishes the buffer, panicking if it is invalid. See [`BufferBuilder::try_finish`].
     pub fn finish(self) -> Buffer {
+        self.try_finish().unwrap_or_else(|e| panic!("failed to create buffer: {e}"))
+    }
+
+    /// Finishes the buffer, which is filled with the contents or the mapped initialization if
+    /// either was given and is zeroed otherwise.
+    pub fn try_finish(self) -> Result<Buffer, Error> {
+        let size = self.contents.map_or(self.size, |contents| contents.len() as u64);
+        self.validate(size)?;
+
         if let Some(contents) = self.contents {
-            self.device.0.device.create_buffer_init(&BufferInitDescriptor {
+            return Ok(self.device.device().create_buffer_init(&BufferInitDescriptor {
                 label: self.label,
                 contents,
                 usage: self.usage,
-            })
-        } else {
-            self.device.0.device.create_buffer(&BufferDescriptor {
-                label: self.label,
-                size: self.size,
-                usage: self.usage,
-                mapped_at_creation: true,
-            })
+            }));
+        }
+
+        let buffer = self.device.device().create_buffer(&BufferDescriptor {
+            label: self.label,
+            size,
+            usage: self.usage,
+            mapped_at_creation: self.init.is_some(),
+        });
+        if let Some(init) = self.init {
+            init(&mut buffer.slice(..).get_mapped_range_mut());
+            buffer.unmap();
         }
+        Ok(buffer)
     }
 
-    pub fn vert(mut self) -> Self {
-        self.usage.insert(BufferUsages::VERTEX);
-        self
+    fn validate(&self, size: u64) -> Result<(), Error> {
+        if size == 0 {
+            return Err(Error::EmptyBuffer);
+        }
+        let max = self.device.device().limits().max_buffer_size;
+        if size > max {
+            return Err(Error::BufferTooLarge(size, max));
+        }
+        if self.init.is_some() && !size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(Error::UnalignedBufferSize(size, wgpu::COPY_BUFFER_ALIGNMENT));
+        }
+
+        // Without this feature, buffers mapped for reading may only be copied into and buffers
+        // mapped for writing may only be copied from.
+        let primary = self.device.device().features().contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS);
+        let allowed = |map: BufferUsages, copy: BufferUsages| {
+            primary || !self.usage.contains(map) || (map | copy).contains(self.usage)
+        };
+        let valid = !self.usage.is_empty()
+            && allowed(BufferUsages::MAP_READ, BufferUsages::COPY_DST)
+            && allowed(BufferUsages::MAP_WRITE, BufferUsages::COPY_SRC);
+        if !valid {
+            return Err(Error::InvalidBufferUsage(self.usage));
+        }
+        Ok(())
     }
 
-    pub fn uniform(mut self) -> Self {
-        self.usage.insert(BufferUsages::UNIFORM);
-        self
+    /// Finishes the buffer as an array of `T`, whose length is the size of the buffer divided by
+    /// the size of `T`.
+    pub fn finish_typed<T: Pod>(self) -> TypedBuffer<T> {
+        let device = self.device.clone();
+        TypedBuffer::from_raw(device, self.finish())
+    }
+
+    pub fn try_finish_typed<T: Pod>(self) -> Result<TypedBuffer<T>, Error> {
+        let device = self.device.clone();
+        Ok(TypedBuffer::from_raw(device, self.try_finish()?))
+    }
+
+    /// Finishes a buffer of `T` that grows when more elements are written than it can hold,
+    /// panicking if it is invalid. See [`BufferBuilder::try_finish_dynamic`].
+    pub fn finish_dynamic<T: Pod>(self) -> DynamicBuffer<T> {
+        self.try_finish_dynamic().unwrap_or_else(|e| panic!("failed to create buffer: {e}"))
     }
 
-    pub fn copy_dst(mut self) -> Self {
+    /// Finishes a buffer of `T` that grows when more elements are written than it can hold. Its
+    /// initial capacity is the size of the builder or of the contents in elements, whichever is
+    /// larger, and it always allows copying into.
+    ///
+    /// Fails if the contents are not a whole number of `T`, or if a mapped initialization was
+    /// given, as the buffer is not mapped at creation.
+    pub fn try_finish_dynamic<T: Pod>(mut self) -> Result<DynamicBuffer<T>, Error> {
+        let stride = TypedBuffer::<T>::stride();
+        let contents = self.contents.unwrap_or_default();
+        if self.init.is_some() {
+            return Err(Error::MappedDynamicBuffer);
+        }
+        if !(contents.len() as u64).is_multiple_of(stride) {
+            return Err(Error::UnalignedBufferSize(contents.len() as u64, stride));
+        }
         self.usage.insert(BufferUsages::COPY_DST);
+        let capacity = (self.size / stride).max(contents.len() as u64 / stride);
+        self.validate(dynamic_size::<T>(capacity))?;
+
+        let mut buffer = DynamicBuffer::new(self.device, self.label.map(str::to_owned), self.usage, capacity);
+        if !contents.is_empty() {
+            let mut data = vec![T::zeroed(); contents.len() / size_of::<T>()];
+            bytemuck::cast_slice_mut::<T, u8>(&mut data).copy_from_slice(contents);
+            buffer.write(&data);
+        }
+        Ok(buffer)
+    }
+
+    /// Finishes the buffer as indices of the format given to [`BufferBuilder::index`], or
+    /// [`IndexFormat::Uint32`] if none was given.
+    pub fn finish_index(self) -> IndexBuffer {
+        self.try_finish_index().unwrap_or_else(|e| panic!("failed to create buffer: {e}"))
+    }
+
+    pub fn try_finish_index(mut self) -> Result<IndexBuffer, Error> {
+        let format = *self.index_format.get_or_insert(IndexFormat::Uint32);
+        self.usage.insert(BufferUsages::INDEX);
+        let size = self.contents.map_or(self.size, |contents| contents.len() as u64);
+        let len = (size / index_size(format)) as u32;
+        Ok(IndexBuffer::from_raw(self.try_finish()?, format, len))
+    }
+
+    pub fn usage(mut self, usage: BufferUsages) -> Self {
+        self.usage.insert(usage);
         self
     }
+
+    pub fn map_read(self) -> Self {
+        self.usage(BufferUsages::MAP_READ)
+    }
+
+    pub fn map_write(self) -> Self {
+        self.usage(BufferUsages::MAP_WRITE)
+    }
+
+    pub fn copy_src(self) -> Self {
+        self.usage(BufferUsages::COPY_SRC)
+    }
+
+    pub fn copy_dst(self) -> Self {
+        self.usage(BufferUsages::COPY_DST)
+    }
+
+    /// Allows using the buffer as indices of the given format.
+    pub fn index(mut self, format: IndexFormat) -> Self {
+        self.index_format = Some(format);
+        self.usage(BufferUsages::INDEX)
+    }
+
+    pub fn vert(self) -> Self {
+        self.usage(BufferUsages::VERTEX)
+    }
+
+    pub fn uniform(self) -> Self {
+        self.usage(BufferUsages::UNIFORM)
+    }
+
+    pub fn storage(self) -> Self {
+        self.usage(BufferUsages::STORAGE)
+    }
+
+    pub fn indirect(self) -> Self {
+        self.usage(BufferUsages::INDIRECT)
+    }
+
+    pub fn query_resolve(self) -> Self {
+        self.usage(BufferUsages::QUERY_RESOLVE)
+    }
+
+    /// A storage buffer that shaders write and that can be written from and copied back to the
+    /// CPU.
+    pub fn storage_rw(self) -> Self {
+        self.storage().copy_src().copy_dst()
+    }
+
+    /// Arguments of indirect draws or dispatches, which compute shaders or the queue may write.
+    pub fn indirect_args(self) -> Self {
+        self.indirect().storage().copy_dst()
+    }
+
+    /// A staging buffer that other buffers are copied into to read them on the CPU.
+    pub fn readback(self) -> Self {
+        self.map_read().copy_dst()
+    }
 }
 
 impl Device {
@@ -65,8 +235,306 @@ impl Device {
             label: None,
             device: self.clone(),
             contents: None,
+            init: None,
             size: 0,
             usage: BufferUsages::empty(),
+            index_format: None,
         }
     }
-}
\ No newline at end of file
+}
+
+/// A buffer of indices that remembers their format.
+#[derive(Debug)]
+pub struct IndexBuffer {
+    buffer: Buffer,
+    format: IndexFormat,
+    len: u32,
+}
+
+impl IndexBuffer {
+    pub fn from_raw(buffer: Buffer, format: IndexFormat, len: u32) -> Self {
+        Self { buffer, format, len }
+    }
+
+    pub fn format(&self) -> IndexFormat {
+        self.format
+    }
+
+    /// The number of indices the buffer holds.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sets this as the index buffer of a render pass.
+    pub fn bind(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_index_buffer(self.buffer.slice(..), self.format);
+    }
+
+    pub fn raw(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn into_raw(self) -> Buffer {
+        self.buffer
+    }
+}
+
+fn index_size(format: IndexFormat) -> u64 {
+    match format {
+        IndexFormat::Uint16 => 2,
+        IndexFormat::Uint32 => 4,
+    }
+}
+
+impl std::ops::Deref for IndexBuffer {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+/// A buffer holding an array of `T`, addressed in elements rather than bytes.
+///
+/// Dereferences to the underlying [`wgpu::Buffer`], so it can be bound like any other buffer.
+#[derive(Debug)]
+pub struct TypedBuffer<T: Pod> {
+    device: Device,
+    buffer: Buffer,
+    len: u64,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> TypedBuffer<T> {
+    /// Wraps a buffer, which holds as many elements as fit in its size.
+    pub fn from_raw(device: Device, buffer: Buffer) -> Self {
+        Self {
+            len: buffer.size() / Self::stride(),
+            device,
+            buffer,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The size of one element in bytes.
+    pub const fn stride() -> BufferAddress {
+        const { assert!(size_of::<T>() != 0, "buffers cannot hold zero-sized elements") };
+        size_of::<T>() as BufferAddress
+    }
+
+    /// The number of elements the buffer holds.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Writes elements through the queue, starting at the element at `index`.
+    ///
+    /// Panics if the elements do not fit in the buffer, or if the write does not start and end on
+    /// a multiple of [`wgpu::COPY_BUFFER_ALIGNMENT`] bytes, which elements smaller than that
+    /// alignment must be written in groups to satisfy.
+    pub fn write(&self, index: u64, data: &[T]) {
+        let end = index.checked_add(data.len() as u64);
+        assert!(
+            end.is_some_and(|end| end <= self.len),
+            "writing {} elements at {index} overflows a buffer of {} elements",
+            data.len(),
+            self.len,
+        );
+        let offset = index * Self::stride();
+        let size = data.len() as u64 * Self::stride();
+        assert!(
+            offset.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) && size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            "writing {} elements at {index} does not start and end on a multiple of {} bytes",
+            data.len(),
+            wgpu::COPY_BUFFER_ALIGNMENT,
+        );
+        self.device.queue().write_buffer(&self.buffer, offset, bytemuck::cast_slice(data));
+    }
+
+    /// Slices the buffer in elements, e.g. to pass to `set_vertex_buffer`.
+    ///
+    /// Panics if the range is empty or extends past the end of the buffer.
+    pub fn slice(&self, range: impl RangeBounds<u64>) -> BufferSlice<'_> {
+        let (start, end) = match resolve_range(&range, self.len) {
+            Ok(range) if !range.is_empty() => (range.start, range.end),
+            _ => panic!(
+                "element range ({:?}, {:?}) is empty or out of bounds of a buffer of {} elements",
+                range.start_bound(),
+                range.end_bound(),
+                self.len,
+            ),
+        };
+        self.buffer.slice(start * Self::stride()..end * Self::stride())
+    }
+
+    pub fn raw(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn into_raw(self) -> Buffer {
+        self.buffer
+    }
+}
+
+impl<T: Pod> std::ops::Deref for TypedBuffer<T> {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl<T: Pod> AsRef<Buffer> for TypedBuffer<T> {
+    fn as_ref(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
+/// A buffer of `T` whose contents are replaced each time it is written, e.g. for instances or
+/// sprites that change every frame.
+///
+/// When more elements are written than fit, the underlying [`wgpu::Buffer`] is replaced by one
+/// at least twice as large. Bind groups referring to the buffer must then be recreated, which
+/// [`DynamicBuffer::write`] reports and [`DynamicBuffer::generation`] tracks.
+#[derive(Debug)]
+pub struct DynamicBuffer<T: Pod> {
+    device: Device,
+    label: Option<String>,
+    usage: BufferUsages,
+    buffer: TypedBuffer<T>,
+    len: u64,
+    generation: u64,
+}
+
+impl<T: Pod> DynamicBuffer<T> {
+    pub fn new(device: Device, label: Option<String>, usage: BufferUsages, capacity: u64) -> Self {
+        let usage = usage | BufferUsages::COPY_DST;
+        let buffer = create_dynamic(&device, label.as_deref(), usage, capacity);
+        Self {
+            device,
+            label,
+            usage,
+            buffer,
+            len: 0,
+            generation: 0,
+        }
+    }
+
+    /// Replaces the contents of the buffer, returning whether the underlying buffer had to be
+    /// reallocated to fit them.
+    pub fn write(&mut self, data: &[T]) -> bool {
+        let reallocated = self.reserve(data.len() as u64);
+        self.len = data.len() as u64;
+
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        let aligned = bytes.len().next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize);
+        if aligned == bytes.len() {
+            self.device.queue().write_buffer(&self.buffer, 0, bytes);
+        } else {
+            let mut padded = bytes.to_vec();
+            padded.resize(aligned, 0);
+            self.device.queue().write_buffer(&self.buffer, 0, &padded);
+        }
+        reallocated
+    }
+
+    /// Makes room for at least `capacity` elements, returning whether the underlying buffer was
+    /// reallocated. Reallocating discards the current contents.
+    pub fn reserve(&mut self, capacity: u64) -> bool {
+        if capacity <= self.capacity() {
+            return false;
+        }
+        let capacity = capacity.max(self.capacity() * 2);
+        self.buffer = create_dynamic(&self.device, self.label.as_deref(), self.usage, capacity);
+        self.len = 0;
+        self.generation += 1;
+        true
+    }
+
+    /// The number of elements last written.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of elements the buffer can hold before it is reallocated.
+    pub fn capacity(&self) -> u64 {
+        self.buffer.len()
+    }
+
+    /// Incremented every time the underlying buffer is reallocated.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Slices the elements last written, e.g. to pass to `set_vertex_buffer`, or returns `None`
+    /// if none were.
+    pub fn slice(&self) -> Option<BufferSlice<'_>> {
+        (self.len > 0).then(|| self.buffer.slice(..self.len))
+    }
+
+    pub fn raw(&self) -> &Buffer {
+        self.buffer.raw()
+    }
+}
+
+impl<T: Pod> std::ops::Deref for DynamicBuffer<T> {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer.raw()
+    }
+}
+
+impl<T: Pod> AsRef<Buffer> for DynamicBuffer<T> {
+    fn as_ref(&self) -> &Buffer {
+        self.buffer.raw()
+    }
+}
+
+/// The size of a dynamic buffer holding `capacity` elements, which holds at least one so that it
+/// can be bound.
+fn dynamic_size<T: Pod>(capacity: u64) -> u64 {
+    (capacity.max(1) * TypedBuffer::<T>::stride()).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+}
+
+fn create_dynamic<T: Pod>(device: &Device, label: Option<&str>, usage: BufferUsages, capacity: u64) -> TypedBuffer<T> {
+    let buffer = device.device().create_buffer(&BufferDescriptor {
+        label,
+        size: dynamic_size::<T>(capacity),
+        usage,
+        mapped_at_creation: false,
+    });
+    TypedBuffer::from_raw(device.clone(), buffer)
+}
+
+/// Resolves a range of a buffer of `len` bytes or elements. Returns the requested range, with
+/// bounds that overflow saturated at `u64::MAX`, as the error if it is reversed or extends past
+/// the end.
+pub(crate) fn resolve_range(range: &impl RangeBounds<u64>, len: u64) -> Result<Range<u64>, Range<u64>> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => Some(start),
+        Bound::Excluded(&start) => start.checked_add(1),
+        Bound::Unbounded => Some(0),
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1),
+        Bound::Excluded(&end) => Some(end),
+        Bound::Unbounded => Some(len),
+    };
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end && end <= len => Ok(start..end),
+        (start, end) => Err(start.unwrap_or(u64::MAX)..end.unwrap_or(u64::MAX)),
+    }
+}
@@ -0,0 +1,79 @@
+use crate::gpu;
+use crate::gpu::device::render_pipeline::PipelineLayoutIntermediate;
+use wgpu::*;
+
+pub struct ComputePipelineBuilder<'a> {
+    pub device: gpu::Device,
+    pub label: Option<&'a str>,
+    pub module: &'a ShaderModule,
+    pub entry_point: Option<&'a str>,
+    pub compilation_options: PipelineCompilationOptions<'a>,
+    pub pipeline_layout: PipelineLayoutIntermediate<'a>,
+}
+
+impl<'a> ComputePipelineBuilder<'a> {
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn unlabeled(mut self) -> Self {
+        self.label = None;
+        self
+    }
+
+    pub fn shader(mut self, shader: &'a ShaderModule, entry_point: &'a str) -> Self {
+        self.module = shader;
+        self.entry_point = Some(entry_point);
+        self
+    }
+
+    pub fn entry(mut self, entry_point: &'a str) -> Self {
+        self.entry_point = Some(entry_point);
+        self
+    }
+
+    pub fn bind_group(mut self, bind_group: &'a BindGroupLayout) -> Self {
+        self.pipeline_layout.bind_group_layouts.push(bind_group);
+        self
+    }
+
+    /// Declares a push constant range. Requires [`Device::supports_push_constants`](crate::Device::supports_push_constants).
+    pub fn push_constants(mut self, range: std::ops::Range<u32>) -> Self {
+        self.pipeline_layout.push_constant_ranges.push(PushConstantRange {
+            stages: ShaderStages::COMPUTE,
+            range,
+        });
+        self
+    }
+
+    pub fn finish(&'a self) -> ComputePipeline {
+        let layout = self.device.device().create_pipeline_layout(&PipelineLayoutDescriptor {
+            bind_group_layouts: self.pipeline_layout.bind_group_layouts.as_slice(),
+            push_constant_ranges: self.pipeline_layout.push_constant_ranges.as_slice(),
+            label: self.pipeline_layout.label,
+        });
+
+        self.device.device().create_compute_pipeline(&ComputePipelineDescriptor {
+            label: self.label,
+            layout: Some(&layout),
+            module: self.module,
+            entry_point: self.entry_point,
+            compilation_options: self.compilation_options.clone(),
+            cache: None,
+        })
+    }
+}
+
+impl gpu::Device {
+    pub fn build_compute_pipeline<'a>(&self, shader: &'a ShaderModule) -> ComputePipelineBuilder<'a> {
+        ComputePipelineBuilder {
+            device: self.clone(),
+            label: None,
+            module: shader,
+            entry_point: Some("cs"),
+            compilation_options: PipelineCompilationOptions::default(),
+            pipeline_layout: PipelineLayoutIntermediate::default(),
+        }
+    }
+}
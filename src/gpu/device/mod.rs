@@ -2,8 +2,10 @@ use pollster::FutureExt;
 use crate::Error;
 
 pub mod render_pipeline;
+pub mod compute_pipeline;
 pub mod buffer;
 pub mod bind_group;
+pub mod readback;
 
 #[derive(Debug, Clone)]
 pub struct Device(std::sync::Arc<DeviceOwned>);
@@ -19,11 +21,48 @@ impl Device {
         let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::default(),
             force_fallback_adapter: false,
-            compatible_surface: Some(&surface),
+            compatible_surface: Some(surface),
         }).block_on().ok_or(Error::RequestAdapter)?;
-        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor::default(), None).block_on()?;
 
-        Ok(Self(std::sync::Arc::new(DeviceOwned { adapter, device, queue })))
+        Self::from_adapter(adapter)
+    }
+
+    /// Creates a device that is not tied to any surface, falling back to a software adapter when
+    /// no hardware adapter is available.
+    pub fn headless(instance: &wgpu::Instance) -> Result<Self, Error> {
+        let request = |force_fallback_adapter| instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            force_fallback_adapter,
+            compatible_surface: None,
+        }).block_on();
+        let adapter = request(false).or_else(|| request(true)).ok_or(Error::RequestAdapter)?;
+
+        Self::from_adapter(adapter)
+    }
+
+    fn from_adapter(adapter: wgpu::Adapter) -> Result<Self, Error> {
+        // Software and downlevel adapters may not meet the default limits.
+        let mut required_limits = if wgpu::Limits::default().check_limits(&adapter.limits()) {
+            wgpu::Limits::default()
+        } else {
+            wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits())
+        };
+        // Push constants are native-only, so they are enabled where the adapter supports them.
+        let required_features = adapter.features() & wgpu::Features::PUSH_CONSTANTS;
+        if required_features.contains(wgpu::Features::PUSH_CONSTANTS) {
+            required_limits.max_push_constant_size = adapter.limits().max_push_constant_size;
+        }
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+            required_features,
+            required_limits,
+            ..Default::default()
+        }, None).block_on()?;
+
+        Ok(Self(std::sync::Arc::new(DeviceOwned {
+            adapter,
+            device,
+            queue,
+        })))
     }
 
     pub fn borrow(&self) -> (&wgpu::Adapter, &wgpu::Device, &wgpu::Queue) {
@@ -42,6 +81,22 @@ impl Device {
         &self.0.queue
     }
 
+    pub fn supports_sample_count(&self, format: wgpu::TextureFormat, sample_count: u32) -> bool {
+        if sample_count == 1 {
+            return true;
+        }
+        // Counts other than 4 are only guaranteed with adapter-specific format features.
+        let adapter_specific = self.device().features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        let flags = self.adapter().get_texture_format_features(format).flags;
+        flags.sample_count_supported(sample_count) && (sample_count == 4 || adapter_specific)
+    }
+
+    /// Whether pipelines can declare push constant ranges, up to [`wgpu::Limits::max_push_constant_size`].
+    pub fn supports_push_constants(&self) -> bool {
+        self.device().features().contains(wgpu::Features::PUSH_CONSTANTS)
+    }
+
     pub fn create_encoder(&self) -> wgpu::CommandEncoder {
         self.0.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default())
     }
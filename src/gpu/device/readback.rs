@@ -0,0 +1,118 @@
+use crate::buffer::resolve_range;
+use crate::{Device, Error};
+use bytemuck::Pod;
+use pollster::FutureExt;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::{Range, RangeBounds};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+/// A read of a buffer's contents that completes once its staging copy is mapped.
+///
+/// The copy is only mapped while the device is polled, so something must call
+/// [`wgpu::Device::poll`], e.g. once per frame or with [`wgpu::Maintain::Wait`] on another thread,
+/// for the future to complete.
+pub struct ReadBuffer<T: Pod> {
+    staging: wgpu::Buffer,
+    /// The bytes of the staging buffer holding the requested range.
+    bytes: Range<usize>,
+    state: Arc<Mutex<MapState>>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Future for ReadBuffer<T> {
+    type Output = Result<Vec<T>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        let Some(result) = state.result.take() else {
+            state.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        };
+        drop(state);
+        result?;
+
+        let mut data = vec![T::zeroed(); self.bytes.len() / size_of::<T>()];
+        bytemuck::cast_slice_mut::<T, u8>(&mut data)
+            .copy_from_slice(&self.staging.slice(..).get_mapped_range()[self.bytes.clone()]);
+        self.staging.unmap();
+        Poll::Ready(Ok(data))
+    }
+}
+
+impl Device {
+    /// Copies a byte range of a buffer to the CPU, blocking until the copy completes. The buffer
+    /// must allow [`wgpu::BufferUsages::COPY_SRC`] and the range must hold a whole number of `T`.
+    pub fn read_buffer<T: Pod>(&self, buffer: &wgpu::Buffer, range: impl RangeBounds<u64>) -> Result<Vec<T>, Error> {
+        let read = self.begin_read_buffer(buffer, range)?;
+        self.device().poll(wgpu::Maintain::Wait);
+        read.block_on()
+    }
+
+    /// Copies a byte range of a buffer to the CPU. See [`Device::read_buffer`] and [`ReadBuffer`],
+    /// as the device must be polled for the read to complete.
+    pub async fn read_buffer_async<T: Pod>(
+        &self,
+        buffer: &wgpu::Buffer,
+        range: impl RangeBounds<u64>,
+    ) -> Result<Vec<T>, Error> {
+        self.begin_read_buffer(buffer, range)?.await
+    }
+
+    /// Submits a copy of a byte range of a buffer into a staging buffer and starts mapping it.
+    pub fn begin_read_buffer<T: Pod>(
+        &self,
+        buffer: &wgpu::Buffer,
+        range: impl RangeBounds<u64>,
+    ) -> Result<ReadBuffer<T>, Error> {
+        const { assert!(size_of::<T>() != 0, "cannot read zero-sized elements") };
+        let range = resolve_range(&range, buffer.size())
+            .map_err(|range| Error::BufferOutOfRange(range, buffer.size()))?;
+        let len = range.end - range.start;
+        if !len.is_multiple_of(size_of::<T>() as u64) {
+            return Err(Error::UnalignedBufferSize(len, size_of::<T>() as u64));
+        }
+        if !buffer.usage().contains(wgpu::BufferUsages::COPY_SRC) {
+            return Err(Error::InvalidBufferUsage(buffer.usage()));
+        }
+
+        // Copies must start and end on aligned offsets, so the copied range may be slightly larger.
+        let alignment = wgpu::COPY_BUFFER_ALIGNMENT;
+        let start = range.start - range.start % alignment;
+        let end = range.end.next_multiple_of(alignment);
+        if end > buffer.size() {
+            return Err(Error::BufferOutOfRange(start..end, buffer.size()));
+        }
+
+        let staging = self.build_buffer().label("readback").size((end - start).max(alignment)).readback().try_finish()?;
+        let mut encoder = self.create_encoder();
+        encoder.copy_buffer_to_buffer(buffer, start, &staging, 0, end - start);
+        self.submit_encoder(encoder);
+
+        let state = Arc::new(Mutex::new(MapState::default()));
+        let callback_state = state.clone();
+        staging.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let mut state = callback_state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+
+        let offset = (range.start - start) as usize;
+        Ok(ReadBuffer {
+            staging,
+            bytes: offset..offset + len as usize,
+            state,
+            _marker: PhantomData,
+        })
+    }
+}
@@ -1,6 +1,19 @@
 use crate::gpu;
 use wgpu::*;
 
+/// The format and sample count of the texture a pipeline draws to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderTarget {
+    pub format: TextureFormat,
+    pub sample_count: u32,
+}
+
+impl From<TextureFormat> for RenderTarget {
+    fn from(format: TextureFormat) -> Self {
+        Self { format, sample_count: 1 }
+    }
+}
+
 pub struct RenderPipelineBuilder<'a> {
     pub device: gpu::Device,
     pub label: Option<&'a str>,
@@ -8,6 +21,8 @@ pub struct RenderPipelineBuilder<'a> {
     pub vertex_state: VertexStateIntermediate<'a>,
     pub fragment_state: Option<FragmentStateIntermediate<'a>>,
     pub pipeline_layout: PipelineLayoutIntermediate<'a>,
+    pub depth_stencil: Option<DepthStencilState>,
+    pub multisample: MultisampleState,
 }
 
 impl<'a> RenderPipelineBuilder<'a> {
@@ -98,7 +113,7 @@ impl<'a> RenderPipelineBuilder<'a> {
             module: shader,
             entry_point: Some(entry_point),
             compilation_options: Default::default(),
-            targets: Self::DEFAULT_TARGETS.to_vec(),
+            targets: vec![],
         });
         self
     }
@@ -114,6 +129,59 @@ impl<'a> RenderPipelineBuilder<'a> {
         let shader = self.vertex_state.module;
         self.frag(shader, "fs")
     }
+
+    /// Sets the format of the first color target, adding it if there are no targets yet.
+    pub fn format(mut self, format: TextureFormat) -> Self {
+        let Some(state) = &mut self.fragment_state else { return self };
+        match state.targets.first_mut() {
+            Some(Some(target)) => target.format = format,
+            Some(target) => *target = Some(Self::color_target_state(format)),
+            None => state.targets.push(Some(Self::color_target_state(format))),
+        }
+
+        self
+    }
+
+    /// Adds another color target. Subsequent calls to [`Self::blend`] and [`Self::write_mask`]
+    /// apply to it.
+    pub fn add_target(self, format: TextureFormat) -> Self {
+        self.add_target_state(Some(Self::color_target_state(format)))
+    }
+
+    pub fn add_target_state(mut self, target: Option<ColorTargetState>) -> Self {
+        let Some(state) = &mut self.fragment_state else { return self };
+        state.targets.push(target);
+
+        self
+    }
+
+    /// Sets the blend state of the last color target.
+    pub fn blend(mut self, blend: Option<BlendState>) -> Self {
+        if let Some(target) = self.last_target_mut() {
+            target.blend = blend;
+        }
+        self
+    }
+
+    /// Sets the write mask of the last color target.
+    pub fn write_mask(mut self, write_mask: ColorWrites) -> Self {
+        if let Some(target) = self.last_target_mut() {
+            target.write_mask = write_mask;
+        }
+        self
+    }
+
+    fn last_target_mut(&mut self) -> Option<&mut ColorTargetState> {
+        self.fragment_state.as_mut()?.targets.last_mut()?.as_mut()
+    }
+
+    fn color_target_state(format: TextureFormat) -> ColorTargetState {
+        ColorTargetState {
+            format,
+            blend: Some(BlendState::REPLACE),
+            write_mask: ColorWrites::ALL,
+        }
+    }
 }
 
 #[derive(Default)]
@@ -128,15 +196,75 @@ impl<'a> RenderPipelineBuilder<'a> {
         self.pipeline_layout.bind_group_layouts.push(bind_group);
         self
     }
+
+    /// Declares a push constant range. Requires [`Device::supports_push_constants`](crate::Device::supports_push_constants).
+    pub fn push_constants(mut self, stages: ShaderStages, range: std::ops::Range<u32>) -> Self {
+        self.pipeline_layout.push_constant_ranges.push(PushConstantRange { stages, range });
+        self
+    }
 }
 
-impl<'a> RenderPipelineBuilder<'a> {
-    const DEFAULT_TARGETS: &'static [Option<ColorTargetState>] = &[Some(ColorTargetState {
-        format: TextureFormat::Bgra8UnormSrgb,
-        blend: Some(BlendState::REPLACE),
-        write_mask: ColorWrites::ALL,
-    })];
+impl RenderPipelineBuilder<'_> {
+    /// Enables depth testing and writing against a depth buffer of the given format.
+    pub fn depth(mut self, format: TextureFormat) -> Self {
+        self.depth_stencil = Some(DepthStencilState {
+            format,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::Less,
+            stencil: StencilState::default(),
+            bias: DepthBiasState::default(),
+        });
+        self
+    }
 
+    /// Enables depth testing against the depth buffer of a stage, using its format and comparison.
+    pub fn depth_buffer(self, depth: &crate::DepthBuffer) -> Self {
+        self.depth(depth.format).depth_compare(depth.compare)
+    }
+
+    pub fn depth_compare(mut self, compare: CompareFunction) -> Self {
+        let Some(state) = &mut self.depth_stencil else { return self };
+        state.depth_compare = compare;
+
+        self
+    }
+
+    pub fn depth_write(mut self, enabled: bool) -> Self {
+        let Some(state) = &mut self.depth_stencil else { return self };
+        state.depth_write_enabled = enabled;
+
+        self
+    }
+
+    pub fn depth_bias(mut self, bias: DepthBiasState) -> Self {
+        let Some(state) = &mut self.depth_stencil else { return self };
+        state.bias = bias;
+
+        self
+    }
+
+    pub fn stencil(mut self, stencil: StencilState) -> Self {
+        let Some(state) = &mut self.depth_stencil else { return self };
+        state.stencil = stencil;
+
+        self
+    }
+}
+
+impl RenderPipelineBuilder<'_> {
+    /// Sets the sample count, which must match the texture the pipeline draws to.
+    pub fn sample_count(mut self, count: u32) -> Self {
+        self.multisample.count = count;
+        self
+    }
+
+    pub fn alpha_to_coverage(mut self, enabled: bool) -> Self {
+        self.multisample.alpha_to_coverage_enabled = enabled;
+        self
+    }
+}
+
+impl<'a> RenderPipelineBuilder<'a> {
     pub fn finish(&'a self) -> RenderPipeline {
         let layout = self.device.device().create_pipeline_layout(&PipelineLayoutDescriptor {
             bind_group_layouts: self.pipeline_layout.bind_group_layouts.as_slice(),
@@ -148,12 +276,8 @@ impl<'a> RenderPipelineBuilder<'a> {
             layout: Some(&layout),
             vertex: self.vertex_state.build(),
             primitive: self.primitive_state,
-            depth_stencil: None,
-            multisample: MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
+            depth_stencil: self.depth_stencil.clone(),
+            multisample: self.multisample,
             fragment: self.fragment_state.as_ref().map(|x| x.build()),
             multiview: None,
             cache: None,
@@ -163,8 +287,14 @@ impl<'a> RenderPipelineBuilder<'a> {
 }
 
 impl gpu::Device {
-    pub fn build_pipeline<'a>(&self, shader: &'a ShaderModule) -> RenderPipelineBuilder<'a> {
-        self.build_vert_pipeline(shader).default_frag()
+    /// Builds a pipeline with the `vs` and `fs` entry points of a shader and one color target,
+    /// usually [`Resources::surface_target`](crate::Resources::surface_target) to draw in stages.
+    pub fn build_pipeline<'a>(&self, shader: &'a ShaderModule, target: impl Into<RenderTarget>) -> RenderPipelineBuilder<'a> {
+        let target = target.into();
+        self.build_vert_pipeline(shader)
+            .default_frag()
+            .format(target.format)
+            .sample_count(target.sample_count)
     }
 
     pub fn build_vert_pipeline<'a>(&self, shader: &'a ShaderModule) -> RenderPipelineBuilder<'a> {
@@ -175,6 +305,12 @@ impl gpu::Device {
             primitive_state: PrimitiveState::default(),
             fragment_state: None,
             pipeline_layout: PipelineLayoutIntermediate::default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
         }
     }
 }
\ No newline at end of file
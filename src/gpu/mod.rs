@@ -1,35 +1,62 @@
+pub mod attachment;
+pub mod capture;
 pub mod device;
+pub mod options;
 pub mod surface;
 
+pub use attachment::*;
+pub use capture::*;
 pub use device::*;
+pub use options::*;
 pub use surface::*;
 
-use crate::{Resources, Resolution, Error, Stages};
+use crate::{FrameContext, Resources, Resolution, Error, Stages, TypeMap};
+use crate::render_pipeline::RenderPipelineBuilder;
 
 pub struct Gpu<'w> {
     device: Device,
     surface: Surface<'w>,
     resources: Resources,
+    scratch: TypeMap,
 }
 
 impl<'w> Gpu<'w> {
     pub fn new(
         resolution: impl Resolution<u32>,
-        target: impl Into<wgpu::SurfaceTarget<'w>>
+        target: impl Into<wgpu::SurfaceTarget<'w>>,
+        options: SurfaceOptions,
     ) -> Result<Self, Error> {
         let instance = wgpu::Instance::default();
         let surface = instance.create_surface(target)?;
         let device = Device::new(&instance, &surface)?;
-        let surface = Surface::new(device.clone(), surface, resolution);
-        let resources = Resources::new(device.clone());
+        let surface = Surface::new(device.clone(), surface, resolution, options)?;
+        let resources = Resources::new(device.clone(), surface.target());
 
-        Ok(Self { device, surface, resources })
+        Ok(Self { device, surface, resources, scratch: TypeMap::new() })
+    }
+
+    pub fn headless(
+        resolution: impl Resolution<u32>,
+        format: wgpu::TextureFormat,
+        options: SurfaceOptions,
+    ) -> Result<Self, Error> {
+        let instance = wgpu::Instance::default();
+        let device = Device::headless(&instance)?;
+        let surface = Surface::offscreen(device.clone(), resolution, format, options)?;
+        let resources = Resources::new(device.clone(), surface.target());
+
+        Ok(Self { device, surface, resources, scratch: TypeMap::new() })
     }
 
     pub fn borrow_mut(&mut self) -> (&Device, &mut Surface<'w>, &mut Resources) {
         (&self.device, &mut self.surface, &mut self.resources)
     }
 
+    /// Borrows what tasks access while updating.
+    pub fn borrow_frame_mut(&mut self) -> (&Device, &mut Resources, &mut TypeMap) {
+        (&self.device, &mut self.resources, &mut self.scratch)
+    }
+
     pub fn device(&self) -> &Device {
         &self.device
     }
@@ -42,9 +69,53 @@ impl<'w> Gpu<'w> {
         &mut self.surface
     }
 
-    pub fn render(&mut self, render_passes: &mut Stages) {
-        render_passes.update(self);
-        render_passes.render(self);
+    /// Reconfigures the surface with new options and updates the target reported to tasks.
+    /// [`Renderer::reconfigure`](crate::Renderer::reconfigure) also notifies the tasks.
+    pub fn reconfigure(&mut self, options: SurfaceOptions) -> Result<(), Error> {
+        let result = self.surface.reconfigure(options);
+        self.resources.set_surface_target(self.surface.target());
+        result
+    }
+
+    /// Sets the sample count of the surface and updates the target reported to tasks.
+    pub fn set_sample_count(&mut self, sample_count: u32) -> Result<(), Error> {
+        self.surface.set_sample_count(sample_count)?;
+        self.resources.set_surface_target(self.surface.target());
+        Ok(())
+    }
+
+    /// Switches the present mode of the surface without recreating it.
+    pub fn set_vsync(&mut self, vsync: VSync) {
+        self.surface.set_vsync(vsync);
+    }
+
+    /// Builds a pipeline drawing to the surface. See [`Device::build_pipeline`].
+    pub fn build_pipeline<'a>(&self, shader: &'a wgpu::ShaderModule) -> RenderPipelineBuilder<'a> {
+        self.device.build_pipeline(shader, self.surface.target())
+    }
+
+    pub fn render(&mut self, render_passes: &mut Stages, frame: &FrameContext) -> Result<(), Error> {
+        self.clear_scratch(frame);
+        render_passes.update(self, frame)?;
+        render_passes.render(self, frame)
+    }
+
+    pub fn render_and_capture(&mut self, render_passes: &mut Stages, frame: &FrameContext) -> Result<Image, Error> {
+        self.clear_scratch(frame);
+        render_passes.update(self, frame)?;
+        render_passes.capture(self, frame)
+    }
+
+    /// Clears the values tasks shared during the previous frame. Frames that run no updates, such
+    /// as those between two steps of a fixed timestep, keep them so tasks still render with them.
+    fn clear_scratch(&mut self, frame: &FrameContext) {
+        if frame.updates().next().is_some() {
+            self.scratch = TypeMap::new();
+        }
+    }
+
+    pub fn resolution(&self) -> (u32, u32) {
+        (self.surface.width(), self.surface.height())
     }
 
     pub fn res(&self) -> &Resources {
@@ -54,5 +125,14 @@ impl<'w> Gpu<'w> {
     pub fn res_mut(&mut self) -> &mut Resources {
         &mut self.resources
     }
+
+    /// The values tasks share during the current frame.
+    pub fn scratch(&self) -> &TypeMap {
+        &self.scratch
+    }
+
+    pub fn scratch_mut(&mut self) -> &mut TypeMap {
+        &mut self.scratch
+    }
 }
 
@@ -0,0 +1,176 @@
+use wgpu::{CompositeAlphaMode, PresentMode, SurfaceCapabilities, TextureFormat};
+
+/// Preferences used when configuring a window surface. Each preference falls back to a supported
+/// alternative when the adapter cannot satisfy it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SurfaceOptions {
+    pub vsync: VSync,
+    pub format: FormatPreference,
+    pub alpha: AlphaPreference,
+    pub frame_latency: u32,
+    pub sample_count: u32,
+}
+
+impl Default for SurfaceOptions {
+    fn default() -> Self {
+        Self {
+            vsync: VSync::default(),
+            format: FormatPreference::default(),
+            alpha: AlphaPreference::default(),
+            frame_latency: 2,
+            sample_count: 1,
+        }
+    }
+}
+
+impl SurfaceOptions {
+    pub fn with_vsync(mut self, vsync: VSync) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    pub fn with_format(mut self, format: FormatPreference) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: AlphaPreference) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn with_frame_latency(mut self, frame_latency: u32) -> Self {
+        self.frame_latency = frame_latency;
+        self
+    }
+
+    /// Sets the number of samples per pixel used for multisample anti-aliasing.
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    pub fn select_present_mode(&self, capabilities: &SurfaceCapabilities) -> PresentMode {
+        let preferred: &[PresentMode] = match self.vsync {
+            VSync::On => &[PresentMode::Fifo],
+            VSync::Off => &[PresentMode::Immediate, PresentMode::Mailbox, PresentMode::Fifo],
+            VSync::Mailbox => &[PresentMode::Mailbox, PresentMode::Fifo],
+        };
+        preferred.iter()
+            .find(|mode| capabilities.present_modes.contains(mode))
+            .copied()
+            .unwrap_or(capabilities.present_modes[0])
+    }
+
+    pub fn select_format(&self, capabilities: &SurfaceCapabilities) -> TextureFormat {
+        let formats = &capabilities.formats;
+        let srgb = formats.iter().find(|f| f.is_srgb());
+        let selected = match self.format {
+            FormatPreference::Srgb => srgb,
+            FormatPreference::Linear => formats.iter().find(|f| !f.is_srgb() && !is_hdr(f)),
+            FormatPreference::Hdr => formats.iter().find(|f| is_hdr(f)).or(srgb),
+        };
+        selected.copied().unwrap_or(formats[0])
+    }
+
+    pub fn select_alpha_mode(&self, capabilities: &SurfaceCapabilities) -> CompositeAlphaMode {
+        let preferred = match self.alpha {
+            AlphaPreference::Opaque => CompositeAlphaMode::Opaque,
+            AlphaPreference::Premultiplied => CompositeAlphaMode::PreMultiplied,
+        };
+        [preferred, CompositeAlphaMode::Auto].iter()
+            .find(|mode| capabilities.alpha_modes.contains(mode))
+            .copied()
+            .unwrap_or(capabilities.alpha_modes[0])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VSync {
+    /// Waits for the vertical blank before presenting. Always supported.
+    #[default]
+    On,
+    /// Presents immediately and may tear, falling back to mailbox and then to vsync.
+    Off,
+    /// Replaces the queued frame without tearing when mailbox presentation is available, falling
+    /// back to vsync.
+    Mailbox,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FormatPreference {
+    #[default]
+    Srgb,
+    Linear,
+    /// Prefers a floating point format, falling back to sRGB.
+    Hdr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaPreference {
+    #[default]
+    Opaque,
+    Premultiplied,
+}
+
+fn is_hdr(format: &TextureFormat) -> bool {
+    matches!(format, TextureFormat::Rgba16Float | TextureFormat::Rgba32Float)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capabilities(formats: &[TextureFormat], present_modes: &[PresentMode]) -> SurfaceCapabilities {
+        SurfaceCapabilities {
+            formats: formats.to_vec(),
+            present_modes: present_modes.to_vec(),
+            alpha_modes: vec![CompositeAlphaMode::Opaque],
+            ..Default::default()
+        }
+    }
+
+    fn present_mode(vsync: VSync, modes: &[PresentMode]) -> PresentMode {
+        SurfaceOptions::default().with_vsync(vsync).select_present_mode(&capabilities(&[], modes))
+    }
+
+    fn format(preference: FormatPreference, formats: &[TextureFormat]) -> TextureFormat {
+        SurfaceOptions::default().with_format(preference).select_format(&capabilities(formats, &[]))
+    }
+
+    #[test]
+    fn present_mode_falls_back_to_fifo() {
+        use PresentMode::*;
+        assert_eq!(present_mode(VSync::Mailbox, &[Fifo, Immediate]), Fifo);
+        assert_eq!(present_mode(VSync::Mailbox, &[Immediate, Mailbox, Fifo]), Mailbox);
+        assert_eq!(present_mode(VSync::Off, &[Fifo, Mailbox]), Mailbox);
+        assert_eq!(present_mode(VSync::Off, &[Fifo, Mailbox, Immediate]), Immediate);
+        assert_eq!(present_mode(VSync::On, &[Immediate, Fifo]), Fifo);
+    }
+
+    #[test]
+    fn format_prefers_srgb_and_falls_back_to_the_first() {
+        use TextureFormat::*;
+        assert_eq!(format(FormatPreference::Srgb, &[Bgra8Unorm, Rgba8UnormSrgb]), Rgba8UnormSrgb);
+        assert_eq!(format(FormatPreference::Srgb, &[Bgra8Unorm, Rgba8Unorm]), Bgra8Unorm);
+        assert_eq!(format(FormatPreference::Linear, &[Bgra8UnormSrgb, Rgba16Float, Rgb10a2Unorm]), Rgb10a2Unorm);
+    }
+
+    #[test]
+    fn hdr_prefers_float_formats() {
+        use TextureFormat::*;
+        assert_eq!(format(FormatPreference::Hdr, &[Bgra8UnormSrgb, Rgba16Float]), Rgba16Float);
+        assert_eq!(format(FormatPreference::Hdr, &[Rgb10a2Unorm, Bgra8UnormSrgb]), Bgra8UnormSrgb);
+        assert_eq!(format(FormatPreference::Hdr, &[Rgb10a2Unorm, Bgra8Unorm]), Rgb10a2Unorm);
+    }
+
+    #[test]
+    fn alpha_mode_falls_back_to_auto_then_the_first() {
+        let options = SurfaceOptions::default().with_alpha(AlphaPreference::Premultiplied);
+        let mut capabilities = capabilities(&[], &[]);
+        capabilities.alpha_modes = vec![CompositeAlphaMode::Opaque, CompositeAlphaMode::Auto];
+        assert_eq!(options.select_alpha_mode(&capabilities), CompositeAlphaMode::Auto);
+        capabilities.alpha_modes = vec![CompositeAlphaMode::Inherit];
+        assert_eq!(options.select_alpha_mode(&capabilities), CompositeAlphaMode::Inherit);
+    }
+}
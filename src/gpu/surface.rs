@@ -1,64 +1,288 @@
-use crate::{Device, Resolution};
+use crate::gpu::attachment::Attachment;
+use crate::render_pipeline::RenderTarget;
+use crate::{Device, Error, Resolution, SurfaceOptions, VSync};
 
 #[derive(Debug)]
 pub struct Surface<'w> {
     device: Device,
-    raw: wgpu::Surface<'w>,
+    target: SurfaceTarget<'w>,
     config: wgpu::SurfaceConfiguration,
+    options: SurfaceOptions,
+    sample_count: u32,
+    pub(crate) attachments: Vec<Attachment>,
+    pub(crate) multisample: Option<Attachment>,
+}
+
+#[derive(Debug)]
+enum SurfaceTarget<'w> {
+    Window(wgpu::Surface<'w>),
+    Offscreen(wgpu::Texture),
 }
 
 impl<'w> Surface<'w> {
     pub fn new(
         device: Device,
         surface: wgpu::Surface<'w>,
-        resolution: impl Resolution<u32>
-    ) -> Self {
+        resolution: impl Resolution<u32>,
+        options: SurfaceOptions,
+    ) -> Result<Self, Error> {
         let capabilities = surface.get_capabilities(device.adapter());
-        let format = capabilities.formats.iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap_or(capabilities.formats[0]);
+        // Frames can only be captured when the surface allows copying from its textures.
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (capabilities.usages & wgpu::TextureUsages::COPY_SRC);
+        let config = wgpu::SurfaceConfiguration {
+            usage,
+            format: options.select_format(&capabilities),
+            width: resolution.get_width(),
+            height: resolution.get_height(),
+            present_mode: options.select_present_mode(&capabilities),
+            desired_maximum_frame_latency: options.frame_latency,
+            alpha_mode: options.select_alpha_mode(&capabilities),
+            view_formats: vec![],
+        };
+        let mut surface = Self {
+            device,
+            target: SurfaceTarget::Window(surface),
+            config,
+            options,
+            sample_count: 1,
+            attachments: vec![],
+            multisample: None,
+        };
+        surface.configure();
+        surface.set_sample_count(options.sample_count)?;
+        Ok(surface)
+    }
+
+    /// Creates a surface backed by a texture instead of a window. Only the frame latency and
+    /// sample count of the options apply.
+    pub fn offscreen(
+        device: Device,
+        resolution: impl Resolution<u32>,
+        format: wgpu::TextureFormat,
+        options: SurfaceOptions,
+    ) -> Result<Self, Error> {
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
             format,
             width: resolution.get_width(),
             height: resolution.get_height(),
-            present_mode: capabilities.present_modes[0],
-            desired_maximum_frame_latency: 2,
-            alpha_mode: capabilities.alpha_modes[0],
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: options.frame_latency,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
             view_formats: vec![],
         };
-        surface.configure(device.as_ref(), &config);
-        Self {
+        let texture = create_offscreen_texture(&device, &config);
+        let mut surface = Self {
             device,
-            raw: surface,
+            target: SurfaceTarget::Offscreen(texture),
             config,
-        }
+            options,
+            sample_count: 1,
+            attachments: vec![],
+            multisample: None,
+        };
+        surface.configure();
+        surface.set_sample_count(options.sample_count)?;
+        Ok(surface)
     }
 
-    pub fn get_current_texture(&self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
-        self.raw.get_current_texture()
+    pub fn get_current_texture(&self) -> Result<SurfaceFrame<'_>, wgpu::SurfaceError> {
+        match &self.target {
+            SurfaceTarget::Window(surface) => surface.get_current_texture().map(SurfaceFrame::Window),
+            SurfaceTarget::Offscreen(texture) => Ok(SurfaceFrame::Offscreen(texture)),
+        }
     }
 
-    pub fn current_texture(&self) -> wgpu::SurfaceTexture {
-        self.get_current_texture().unwrap()
+    /// Acquires the next texture to render to, reconfiguring the surface if it was lost or became
+    /// outdated. Returns `None` when no texture is available this frame and the frame should be
+    /// skipped, e.g. when the window is minimized or acquisition timed out.
+    pub fn current_texture(&self) -> Result<Option<SurfaceFrame<'_>>, Error> {
+        if self.is_zero_sized() {
+            return Ok(None);
+        }
+
+        let error = match self.get_current_texture() {
+            Ok(texture) => return Ok(Some(texture)),
+            Err(e) => e,
+        };
+        match error {
+            wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated => {
+                self.configure();
+                match self.get_current_texture() {
+                    Ok(texture) => Ok(Some(texture)),
+                    Err(wgpu::SurfaceError::OutOfMemory) => Err(Error::OutOfMemory),
+                    Err(_) => Ok(None),
+                }
+            }
+            wgpu::SurfaceError::Timeout => Ok(None),
+            wgpu::SurfaceError::OutOfMemory => Err(Error::OutOfMemory),
+        }
     }
 
-    pub fn current_texture_and_view(&self) -> (wgpu::SurfaceTexture, wgpu::TextureView) {
-        let texture = self.get_current_texture().unwrap();
-        let view = texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
-        (texture, view)
+    pub fn current_texture_and_view(&self) -> Result<Option<(SurfaceFrame<'_>, wgpu::TextureView)>, Error> {
+        let Some(texture) = self.current_texture()? else { return Ok(None) };
+        let view = texture.texture().create_view(&wgpu::TextureViewDescriptor::default());
+        Ok(Some((texture, view)))
     }
 
     pub fn resize(&mut self, resolution: impl Resolution<u32>) {
         self.config.width = resolution.get_width();
         self.config.height = resolution.get_height();
-        self.raw.configure(self.device.as_ref(), &self.config);
+        if let SurfaceTarget::Offscreen(texture) = &mut self.target {
+            *texture = create_offscreen_texture(&self.device, &self.config);
+        }
+        self.resize_attachments();
+        self.configure();
+    }
+
+    /// Reselects the present mode, format and alpha mode of a window surface from new options
+    /// without recreating it. Offscreen surfaces keep the format they were created with.
+    ///
+    /// Pipelines built before a format or sample count change must be rebuilt. Prefer
+    /// [`Gpu::reconfigure`](crate::Gpu::reconfigure), which also updates the target reported by
+    /// [`Resources::surface_target`](crate::Resources::surface_target).
+    pub fn reconfigure(&mut self, options: SurfaceOptions) -> Result<(), Error> {
+        self.options = options;
+        self.config.desired_maximum_frame_latency = options.frame_latency;
+        if let SurfaceTarget::Window(surface) = &self.target {
+            let capabilities = surface.get_capabilities(self.device.adapter());
+            self.config.format = options.select_format(&capabilities);
+            self.config.present_mode = options.select_present_mode(&capabilities);
+            self.config.alpha_mode = options.select_alpha_mode(&capabilities);
+        }
+        self.configure();
+        if self.sample_count == options.sample_count {
+            self.resize_attachments();
+            return Ok(());
+        }
+        self.set_sample_count(options.sample_count)
+    }
+
+    pub fn set_vsync(&mut self, vsync: VSync) {
+        self.options.vsync = vsync;
+        if let SurfaceTarget::Window(surface) = &self.target {
+            let capabilities = surface.get_capabilities(self.device.adapter());
+            self.config.present_mode = self.options.select_present_mode(&capabilities);
+        }
+        self.configure();
+    }
+
+    /// Sets the number of samples rendered per pixel, which stages resolve into the surface
+    /// texture. Fails if the surface format or the format of any attachment does not support it.
+    /// Prefer [`Gpu::set_sample_count`](crate::Gpu::set_sample_count), which also updates the
+    /// target reported by [`Resources::surface_target`](crate::Resources::surface_target).
+    pub fn set_sample_count(&mut self, sample_count: u32) -> Result<(), Error> {
+        let formats = std::iter::once(self.config.format)
+            .chain(self.attachments.iter().map(|a| a.format()));
+        for format in formats {
+            if !self.device.supports_sample_count(format, sample_count) {
+                return Err(Error::UnsupportedSampleCount(format, sample_count));
+            }
+        }
+
+        self.sample_count = sample_count;
+        self.options.sample_count = sample_count;
+        self.resize_attachments();
+        Ok(())
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    pub fn options(&self) -> &SurfaceOptions {
+        &self.options
+    }
+
+    /// Applies the current configuration to the window surface. Zero-sized configurations, such
+    /// as those of minimized windows, are deferred until the surface has a size again.
+    pub fn configure(&self) {
+        if let SurfaceTarget::Window(surface) = &self.target && !self.is_zero_sized() {
+            surface.configure(self.device.as_ref(), &self.config);
+        }
+    }
+
+    fn is_zero_sized(&self) -> bool {
+        self.config.width == 0 || self.config.height == 0
+    }
+
+    pub fn get_raw(&self) -> Option<&wgpu::Surface<'w>> {
+        match &self.target {
+            SurfaceTarget::Window(surface) => Some(surface),
+            SurfaceTarget::Offscreen(_) => None,
+        }
+    }
+
+    pub fn is_offscreen(&self) -> bool {
+        matches!(self.target, SurfaceTarget::Offscreen(_))
+    }
+
+    pub fn supports_capture(&self) -> bool {
+        self.config.usage.contains(wgpu::TextureUsages::COPY_SRC)
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn config(&self) -> &wgpu::SurfaceConfiguration {
+        &self.config
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.config.format
+    }
+
+    /// The format and sample count pipelines drawing in stages must use.
+    pub fn target(&self) -> RenderTarget {
+        RenderTarget { format: self.config.format, sample_count: self.sample_count }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.config.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.config.height
     }
 }
 
-impl<'w> AsRef<wgpu::Surface<'w>> for Surface<'w> {
-    fn as_ref(&self) -> &wgpu::Surface<'w> {
-        &self.raw
+fn create_offscreen_texture(device: &Device, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+    device.device().create_texture(&wgpu::TextureDescriptor {
+        label: Some("offscreen surface"),
+        size: wgpu::Extent3d {
+            width: config.width.max(1),
+            height: config.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+        view_formats: &config.view_formats,
+    })
+}
+
+pub enum SurfaceFrame<'s> {
+    Window(wgpu::SurfaceTexture),
+    Offscreen(&'s wgpu::Texture),
+}
+
+impl SurfaceFrame<'_> {
+    pub fn texture(&self) -> &wgpu::Texture {
+        match self {
+            SurfaceFrame::Window(texture) => &texture.texture,
+            SurfaceFrame::Offscreen(texture) => texture,
+        }
     }
-}
\ No newline at end of file
+
+    pub fn present(self) {
+        if let SurfaceFrame::Window(texture) = self {
+            texture.present();
+        }
+    }
+}
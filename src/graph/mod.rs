@@ -0,0 +1,487 @@
+pub mod resource;
+mod pool;
+
+pub use resource::*;
+
+use crate::render_pipeline::RenderTarget;
+use crate::{Error, FrameContext, Gpu};
+use pool::TransientPool;
+use std::any::Any;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Work recorded by a [`RenderGraph`] node each frame.
+pub trait Node: Any {
+    fn initialize(&mut self, _gpu: &mut Gpu) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn update(&mut self, _gpu: &mut Gpu, _frame: &FrameContext) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Records commands before any node of the graph executes.
+    fn prepare(&mut self, _gpu: &Gpu, _frame: &FrameContext, _encoder: &mut wgpu::CommandEncoder) {}
+
+    fn on_resize(&mut self, _gpu: &Gpu, _resolution: (u32, u32)) {}
+
+    fn on_surface_reconfigured(&mut self, _gpu: &Gpu, _target: RenderTarget) {}
+
+    /// Releases what the node holds before the graph is dropped.
+    fn teardown(&mut self, _gpu: &mut Gpu) {}
+
+    fn execute(&mut self, ctx: &mut NodeContext<'_>);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// A node together with the resources it reads and writes, which determine where it runs in the
+/// graph.
+pub struct GraphNode {
+    name: String,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+    render_targets: Vec<ResourceId>,
+    node: Box<dyn Node>,
+    initialized: bool,
+}
+
+impl GraphNode {
+    pub fn new(name: impl Into<String>, node: impl Node + 'static) -> Self {
+        Self {
+            name: name.into(),
+            reads: vec![],
+            writes: vec![],
+            render_targets: vec![],
+            node: Box::new(node),
+            initialized: false,
+        }
+    }
+
+    pub fn reads(mut self, resource: ResourceId) -> Self {
+        self.reads.push(resource);
+        self
+    }
+
+    /// Declares a resource this node writes. Texture writes become attachments of the render pass
+    /// started by [`NodeContext::begin_render_pass`].
+    pub fn writes(mut self, resource: ResourceId) -> Self {
+        self.writes.push(resource);
+        self
+    }
+
+    /// Declares a texture this node writes by rendering to it, which [`RenderGraph::compile`]
+    /// verifies is the surface or a texture rather than a buffer.
+    pub fn renders_to(mut self, resource: ResourceId) -> Self {
+        self.render_targets.push(resource);
+        self.writes(resource)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn node<T: Node>(&self) -> Option<&T> {
+        (self.node.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    pub fn node_mut<T: Node>(&mut self) -> Option<&mut T> {
+        (self.node.as_mut() as &mut dyn Any).downcast_mut()
+    }
+
+    fn uses(&self, resource: usize) -> bool {
+        self.reads.iter().chain(&self.writes).any(|r| r.0 == resource)
+    }
+
+    fn writes_to(&self, resource: usize) -> bool {
+        self.writes.iter().any(|r| r.0 == resource)
+    }
+}
+
+struct Compiled {
+    order: Vec<usize>,
+    lifetimes: Vec<Option<(usize, usize)>>,
+    first_writers: Vec<Option<usize>>,
+}
+
+/// Orders nodes by the resources they read and write and allocates their transient resources.
+///
+/// Writers of a resource run before its readers, and multiple writers of the same resource run in
+/// the order they were added. Transient textures and buffers with identical descriptions share
+/// physical resources when their uses do not overlap.
+pub struct RenderGraph {
+    resources: Vec<ResourceDesc>,
+    nodes: Vec<GraphNode>,
+    compiled: Option<Compiled>,
+    pool: TransientPool,
+}
+
+impl Default for RenderGraph {
+    fn default() -> Self {
+        Self {
+            resources: vec![ResourceDesc::Surface],
+            nodes: vec![],
+            compiled: None,
+            pool: TransientPool::default(),
+        }
+    }
+}
+
+impl RenderGraph {
+    pub fn create_texture(&mut self, desc: TextureDesc) -> ResourceId {
+        self.add_resource(ResourceDesc::Texture(desc))
+    }
+
+    pub fn create_buffer(&mut self, desc: BufferDesc) -> ResourceId {
+        self.add_resource(ResourceDesc::Buffer(desc))
+    }
+
+    fn add_resource(&mut self, desc: ResourceDesc) -> ResourceId {
+        self.resources.push(desc);
+        self.compiled = None;
+        ResourceId(self.resources.len() - 1)
+    }
+
+    pub fn add_node(&mut self, node: GraphNode) -> NodeId {
+        self.nodes.push(node);
+        self.compiled = None;
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &GraphNode> {
+        self.nodes.iter()
+    }
+
+    pub fn get_node(&self, id: NodeId) -> Option<&GraphNode> {
+        self.nodes.get(id.0)
+    }
+
+    pub fn get_node_mut(&mut self, id: NodeId) -> Option<&mut GraphNode> {
+        self.nodes.get_mut(id.0)
+    }
+
+    /// Initializes nodes added since the last call and compiles the graph.
+    pub fn initialize(&mut self, gpu: &mut Gpu) -> Result<(), Error> {
+        for node in self.nodes.iter_mut().filter(|n| !n.initialized) {
+            node.node.initialize(gpu)?;
+            node.initialized = true;
+        }
+        self.compile()
+    }
+
+    /// Initializes nodes added since the last frame and updates all nodes.
+    pub fn update(&mut self, gpu: &mut Gpu, frame: &FrameContext) -> Result<(), Error> {
+        self.initialize(gpu)?;
+        for node in &mut self.nodes {
+            node.node.update(gpu, frame)?;
+        }
+        Ok(())
+    }
+
+    pub fn resize(&mut self, gpu: &Gpu, resolution: (u32, u32)) {
+        for node in &mut self.nodes {
+            node.node.on_resize(gpu, resolution);
+        }
+    }
+
+    pub fn surface_reconfigured(&mut self, gpu: &Gpu, target: RenderTarget) {
+        for node in &mut self.nodes {
+            node.node.on_surface_reconfigured(gpu, target);
+        }
+    }
+
+    /// Tears down every initialized node.
+    pub fn teardown(&mut self, gpu: &mut Gpu) {
+        for node in self.nodes.iter_mut().filter(|n| n.initialized) {
+            node.node.teardown(gpu);
+        }
+    }
+
+    /// Sorts the nodes into execution order, failing if their dependencies form a cycle or a node
+    /// renders to a buffer.
+    pub fn compile(&mut self) -> Result<(), Error> {
+        if self.compiled.is_some() {
+            return Ok(());
+        }
+
+        for node in &self.nodes {
+            if node.render_targets.iter().any(|r| matches!(self.resources[r.0], ResourceDesc::Buffer(_))) {
+                return Err(Error::NotATexture(node.name.clone()));
+            }
+        }
+
+        let mut edges = vec![vec![]; self.nodes.len()];
+        let mut dependencies = vec![0; self.nodes.len()];
+        for resource in 0..self.resources.len() {
+            let writers: Vec<usize> = (0..self.nodes.len())
+                .filter(|&n| self.nodes[n].writes_to(resource))
+                .collect();
+            let readers = (0..self.nodes.len())
+                .filter(|&n| self.nodes[n].uses(resource) && !self.nodes[n].writes_to(resource));
+
+            let mut add_edge = |from: usize, to: usize| {
+                edges[from].push(to);
+                dependencies[to] += 1;
+            };
+            for pair in writers.windows(2) {
+                add_edge(pair[0], pair[1]);
+            }
+            for reader in readers {
+                for &writer in &writers {
+                    add_edge(writer, reader);
+                }
+            }
+        }
+
+        // Among the nodes that are ready, the one added first runs first.
+        let mut ready: BinaryHeap<_> = (0..self.nodes.len())
+            .filter(|&n| dependencies[n] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(Reverse(node)) = ready.pop() {
+            order.push(node);
+            for &next in &edges[node] {
+                dependencies[next] -= 1;
+                if dependencies[next] == 0 {
+                    ready.push(Reverse(next));
+                }
+            }
+        }
+        if let Some(node) = (0..self.nodes.len()).find(|n| !order.contains(n)) {
+            return Err(Error::GraphCycle(self.nodes[node].name.clone()));
+        }
+
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.resources.len()];
+        let mut first_writers = vec![None; self.resources.len()];
+        for (position, &node) in order.iter().enumerate() {
+            let node_ref = &self.nodes[node];
+            for resource in node_ref.reads.iter().chain(&node_ref.writes) {
+                let lifetime = &mut lifetimes[resource.0];
+                *lifetime = Some((lifetime.map_or(position, |(first, _)| first), position));
+            }
+            for resource in &node_ref.writes {
+                first_writers[resource.0].get_or_insert(node);
+            }
+        }
+
+        self.compiled = Some(Compiled { order, lifetimes, first_writers });
+        self.pool.invalidate();
+        Ok(())
+    }
+
+    /// The names of the nodes in the order they execute, if the graph has been compiled.
+    pub fn execution_order(&self) -> Option<Vec<&str>> {
+        let compiled = self.compiled.as_ref()?;
+        Some(compiled.order.iter().map(|&n| self.nodes[n].name()).collect())
+    }
+
+    /// The number of physical textures and buffers backing the transient resources.
+    pub fn allocated_resources(&self) -> usize {
+        self.pool.len()
+    }
+
+    pub fn execute(
+        &mut self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        surface_view: &wgpu::TextureView,
+        frame: &FrameContext,
+    ) -> Result<(), Error> {
+        self.compile()?;
+        let compiled = self.compiled.as_ref().unwrap();
+        let resolution = (gpu.surface().width(), gpu.surface().height());
+        if !self.pool.is_allocated_for(resolution) {
+            self.pool.allocate(gpu.device(), &self.resources, &compiled.lifetimes, resolution);
+        }
+
+        for &node in &compiled.order {
+            self.nodes[node].node.prepare(gpu, frame, encoder);
+        }
+
+        for &node in &compiled.order {
+            let GraphNode { name, writes, node: inner, .. } = &mut self.nodes[node];
+            let mut ctx = NodeContext {
+                gpu,
+                encoder,
+                node,
+                name,
+                writes,
+                resources: &self.resources,
+                pool: &self.pool,
+                first_writers: &compiled.first_writers,
+                surface_view,
+                frame,
+            };
+            inner.execute(&mut ctx);
+        }
+        Ok(())
+    }
+}
+
+/// Gives a node access to the frame's encoder and the resources of the graph while it executes.
+pub struct NodeContext<'a> {
+    gpu: &'a Gpu<'a>,
+    encoder: &'a mut wgpu::CommandEncoder,
+    node: usize,
+    name: &'a str,
+    writes: &'a [ResourceId],
+    resources: &'a [ResourceDesc],
+    pool: &'a TransientPool,
+    first_writers: &'a [Option<usize>],
+    surface_view: &'a wgpu::TextureView,
+    frame: &'a FrameContext,
+}
+
+impl<'a> NodeContext<'a> {
+    pub fn gpu(&self) -> &'a Gpu<'a> {
+        self.gpu
+    }
+
+    pub fn frame(&self) -> &'a FrameContext {
+        self.frame
+    }
+
+    pub fn encoder(&mut self) -> &mut wgpu::CommandEncoder {
+        self.encoder
+    }
+
+    /// The view of a texture of the graph, or `None` if the resource is a buffer.
+    pub fn texture_view(&self, resource: ResourceId) -> Option<&'a wgpu::TextureView> {
+        if resource == ResourceId::SURFACE {
+            return Some(self.surface_view);
+        }
+        self.pool.texture(resource.0).map(|(_, view)| view)
+    }
+
+    pub fn texture(&self, resource: ResourceId) -> Option<&'a wgpu::Texture> {
+        self.pool.texture(resource.0).map(|(texture, _)| texture)
+    }
+
+    /// A buffer of the graph, or `None` if the resource is a texture.
+    pub fn buffer(&self, resource: ResourceId) -> Option<&'a wgpu::Buffer> {
+        self.pool.buffer(resource.0)
+    }
+
+    /// Whether this node is the first in the frame to write the resource.
+    pub fn is_first_write(&self, resource: ResourceId) -> bool {
+        self.first_writers[resource.0] == Some(self.node)
+    }
+
+    /// Begins a render pass with the textures this node writes as attachments. Attachments are
+    /// cleared by the first node that writes them and loaded by later ones.
+    pub fn begin_render_pass(&mut self) -> wgpu::RenderPass<'_> {
+        let mut color_attachments = vec![];
+        let mut depth_stencil_attachment = None;
+        for &resource in self.writes {
+            let clear = self.is_first_write(resource);
+            let format = match &self.resources[resource.0] {
+                ResourceDesc::Surface => self.gpu.surface().format(),
+                ResourceDesc::Texture(desc) => desc.format,
+                ResourceDesc::Buffer(_) => continue,
+            };
+            let Some(view) = self.texture_view(resource) else { continue };
+
+            if format.is_depth_stencil_format() {
+                depth_stencil_attachment = Some(wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: format.has_depth_aspect().then_some(operations(clear, 1.0)),
+                    stencil_ops: format.has_stencil_aspect().then_some(operations(clear, 0)),
+                });
+                continue;
+            }
+
+            let (view, resolve_target) = match self.gpu.surface().multisample_view() {
+                Some(multisample) if resource == ResourceId::SURFACE => (multisample, Some(view)),
+                _ => (view, None),
+            };
+            color_attachments.push(Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target,
+                ops: operations(clear, wgpu::Color::TRANSPARENT),
+            }));
+        }
+
+        self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(self.name),
+            color_attachments: &color_attachments,
+            depth_stencil_attachment,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+}
+
+fn operations<C>(clear: bool, value: C) -> wgpu::Operations<C> {
+    wgpu::Operations {
+        load: if clear { wgpu::LoadOp::Clear(value) } else { wgpu::LoadOp::Load },
+        store: wgpu::StoreOp::Store,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Empty;
+
+    impl Node for Empty {
+        fn execute(&mut self, _: &mut NodeContext<'_>) {}
+    }
+
+    fn texture() -> TextureDesc {
+        TextureDesc::new(wgpu::TextureFormat::Rgba8Unorm)
+    }
+
+    #[test]
+    fn readers_run_after_writers() {
+        let mut graph = RenderGraph::default();
+        let gbuffer = graph.create_texture(texture());
+        graph.add_node(GraphNode::new("composite", Empty).reads(gbuffer).writes(ResourceId::SURFACE));
+        graph.add_node(GraphNode::new("ui", Empty).writes(ResourceId::SURFACE));
+        graph.add_node(GraphNode::new("geometry", Empty).writes(gbuffer));
+
+        graph.compile().unwrap();
+        assert_eq!(graph.execution_order().unwrap(), ["geometry", "composite", "ui"]);
+    }
+
+    #[test]
+    fn cycle_is_an_error() {
+        let mut graph = RenderGraph::default();
+        let a = graph.create_texture(texture());
+        let b = graph.create_texture(texture());
+        graph.add_node(GraphNode::new("first", Empty).reads(a).writes(b));
+        graph.add_node(GraphNode::new("second", Empty).reads(b).writes(a));
+
+        assert!(matches!(graph.compile(), Err(Error::GraphCycle(name)) if name == "first"));
+    }
+
+    #[test]
+    fn rendering_to_a_buffer_is_an_error() {
+        let mut graph = RenderGraph::default();
+        let buffer = graph.create_buffer(BufferDesc::new(256, wgpu::BufferUsages::STORAGE));
+        graph.add_node(GraphNode::new("stage", Empty).renders_to(buffer));
+
+        assert!(matches!(graph.compile(), Err(Error::NotATexture(name)) if name == "stage"));
+    }
+
+    #[test]
+    fn transients_with_disjoint_lifetimes_share_a_texture() {
+        let mut graph = RenderGraph::default();
+        let first = graph.create_texture(texture());
+        let second = graph.create_texture(texture());
+        let overlapping = graph.create_texture(texture());
+        graph.add_node(GraphNode::new("0", Empty).writes(first).writes(overlapping));
+        graph.add_node(GraphNode::new("1", Empty).reads(first).writes(ResourceId::SURFACE));
+        graph.add_node(GraphNode::new("2", Empty).writes(second));
+        graph.add_node(GraphNode::new("3", Empty).reads(second).reads(overlapping).writes(ResourceId::SURFACE));
+
+        graph.compile().unwrap();
+        let plan = pool::plan(&graph.resources, &graph.compiled.as_ref().unwrap().lifetimes, (64, 64));
+        assert_eq!(plan.physical.len(), 2);
+        assert_eq!(plan.assignments[first.0], plan.assignments[second.0]);
+        assert_ne!(plan.assignments[first.0], plan.assignments[overlapping.0]);
+        assert_eq!(plan.assignments[ResourceId::SURFACE.0], None);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::graph::{BufferDesc, ResourceDesc, TextureDesc};
+use crate::Device;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TextureKey {
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    usage: wgpu::TextureUsages,
+    sample_count: u32,
+}
+
+enum Physical {
+    Texture {
+        texture: wgpu::Texture,
+        view: wgpu::TextureView,
+    },
+    Buffer(wgpu::Buffer),
+}
+
+/// Physical textures and buffers backing the transient resources of a graph.
+#[derive(Default)]
+pub(crate) struct TransientPool {
+    physical: Vec<Physical>,
+    /// The physical resource each graph resource is assigned to.
+    assignments: Vec<Option<usize>>,
+    /// The surface resolution the pool was allocated for.
+    resolution: Option<(u32, u32)>,
+}
+
+impl TransientPool {
+    pub(crate) fn is_allocated_for(&self, resolution: (u32, u32)) -> bool {
+        self.resolution == Some(resolution)
+    }
+
+    pub(crate) fn invalidate(&mut self) {
+        self.resolution = None;
+    }
+
+    /// Allocates the physical resources planned by [`plan`].
+    pub(crate) fn allocate(
+        &mut self,
+        device: &Device,
+        resources: &[ResourceDesc],
+        lifetimes: &[Option<(usize, usize)>],
+        resolution: (u32, u32),
+    ) {
+        let plan = plan(resources, lifetimes, resolution);
+        self.physical = plan.physical.iter().map(|&i| match &resources[i] {
+            ResourceDesc::Texture(desc) => create_texture(device, desc, texture_key(desc, resolution)),
+            ResourceDesc::Buffer(desc) => create_buffer(device, desc),
+            ResourceDesc::Surface => unreachable!("the surface is not allocated"),
+        }).collect();
+        self.assignments = plan.assignments;
+        self.resolution = Some(resolution);
+    }
+
+    pub(crate) fn texture(&self, resource: usize) -> Option<(&wgpu::Texture, &wgpu::TextureView)> {
+        match &self.physical[self.assignments.get(resource).copied().flatten()?] {
+            Physical::Texture { texture, view } => Some((texture, view)),
+            Physical::Buffer(_) => None,
+        }
+    }
+
+    pub(crate) fn buffer(&self, resource: usize) -> Option<&wgpu::Buffer> {
+        match &self.physical[self.assignments.get(resource).copied().flatten()?] {
+            Physical::Buffer(buffer) => Some(buffer),
+            Physical::Texture { .. } => None,
+        }
+    }
+
+    /// The number of physical resources backing the graph.
+    pub(crate) fn len(&self) -> usize {
+        self.physical.len()
+    }
+}
+
+/// Which physical resource backs each graph resource.
+pub(crate) struct Plan {
+    /// The graph resource whose description each physical resource is created from.
+    pub(crate) physical: Vec<usize>,
+    pub(crate) assignments: Vec<Option<usize>>,
+}
+
+/// Assigns graph resources to physical resources, letting resources whose lifetimes (the first
+/// and last position in the execution order they are used at) do not overlap share the same one.
+pub(crate) fn plan(resources: &[ResourceDesc], lifetimes: &[Option<(usize, usize)>], resolution: (u32, u32)) -> Plan {
+    let mut plan = Plan { physical: vec![], assignments: vec![None; resources.len()] };
+    let mut by_first_use: Vec<usize> = (0..resources.len())
+        .filter(|&i| lifetimes[i].is_some())
+        .collect();
+    by_first_use.sort_by_key(|&i| lifetimes[i].unwrap().0);
+
+    // The position after which each physical resource is free again.
+    let mut free_after: Vec<usize> = vec![];
+    for i in by_first_use {
+        if matches!(resources[i], ResourceDesc::Surface) {
+            continue;
+        }
+        let (first, last) = lifetimes[i].unwrap();
+        let compatible = |&(p, &other): &(usize, &usize)| {
+            free_after[p] < first && match (&resources[i], &resources[other]) {
+                (ResourceDesc::Texture(a), ResourceDesc::Texture(b)) => {
+                    texture_key(a, resolution) == texture_key(b, resolution)
+                }
+                (ResourceDesc::Buffer(a), ResourceDesc::Buffer(b)) => a.size == b.size && a.usage == b.usage,
+                _ => false,
+            }
+        };
+        let physical = match plan.physical.iter().enumerate().find(compatible) {
+            Some((p, _)) => p,
+            None => {
+                plan.physical.push(i);
+                free_after.push(0);
+                plan.physical.len() - 1
+            }
+        };
+        free_after[physical] = last;
+        plan.assignments[i] = Some(physical);
+    }
+    plan
+}
+
+fn texture_key(desc: &TextureDesc, resolution: (u32, u32)) -> TextureKey {
+    let (width, height) = desc.size.resolve(resolution.0, resolution.1);
+    TextureKey {
+        format: desc.format,
+        width,
+        height,
+        usage: desc.usage,
+        sample_count: desc.sample_count,
+    }
+}
+
+fn create_texture(device: &Device, desc: &TextureDesc, key: TextureKey) -> Physical {
+    let texture = device.device().create_texture(&wgpu::TextureDescriptor {
+        label: desc.label,
+        size: wgpu::Extent3d {
+            width: key.width,
+            height: key.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: key.sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: key.format,
+        usage: key.usage,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    Physical::Texture { texture, view }
+}
+
+fn create_buffer(device: &Device, desc: &BufferDesc) -> Physical {
+    let buffer = device.device().create_buffer(&wgpu::BufferDescriptor {
+        label: desc.label,
+        size: desc.size,
+        usage: desc.usage,
+        mapped_at_creation: false,
+    });
+    Physical::Buffer(buffer)
+}
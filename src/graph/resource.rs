@@ -0,0 +1,106 @@
+/// Identifies a texture or buffer in a [`RenderGraph`](crate::graph::RenderGraph).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceId(pub(crate) usize);
+
+impl ResourceId {
+    /// The surface texture of the current frame, which every graph imports.
+    pub const SURFACE: Self = Self(0);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureSize {
+    /// Matches the resolution of the surface.
+    Surface,
+    /// The resolution of the surface multiplied by a factor.
+    Scaled(f32),
+    Fixed(u32, u32),
+}
+
+impl TextureSize {
+    pub fn resolve(&self, surface_width: u32, surface_height: u32) -> (u32, u32) {
+        let (width, height) = match *self {
+            TextureSize::Surface => (surface_width, surface_height),
+            TextureSize::Scaled(scale) => (
+                (surface_width as f32 * scale) as u32,
+                (surface_height as f32 * scale) as u32,
+            ),
+            TextureSize::Fixed(width, height) => (width, height),
+        };
+        (width.max(1), height.max(1))
+    }
+}
+
+/// Describes a transient texture that the graph allocates and may share between nodes whose
+/// uses do not overlap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureDesc {
+    pub label: Option<&'static str>,
+    pub format: wgpu::TextureFormat,
+    pub size: TextureSize,
+    pub usage: wgpu::TextureUsages,
+    pub sample_count: u32,
+}
+
+impl TextureDesc {
+    pub fn new(format: wgpu::TextureFormat) -> Self {
+        Self {
+            label: None,
+            format,
+            size: TextureSize::Surface,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            sample_count: 1,
+        }
+    }
+
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = TextureSize::Fixed(width, height);
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.size = TextureSize::Scaled(scale);
+        self
+    }
+
+    pub fn with_usage(mut self, usage: wgpu::TextureUsages) -> Self {
+        self.usage.insert(usage);
+        self
+    }
+
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+}
+
+/// Describes a transient buffer that the graph allocates and may share between nodes whose
+/// uses do not overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferDesc {
+    pub label: Option<&'static str>,
+    pub size: u64,
+    pub usage: wgpu::BufferUsages,
+}
+
+impl BufferDesc {
+    pub fn new(size: u64, usage: wgpu::BufferUsages) -> Self {
+        Self { label: None, size, usage }
+    }
+
+    pub fn with_label(mut self, label: &'static str) -> Self {
+        self.label = Some(label);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ResourceDesc {
+    Surface,
+    Texture(TextureDesc),
+    Buffer(BufferDesc),
+}
@@ -1,21 +1,32 @@
 pub mod collections;
 pub mod error;
+pub mod frame;
 pub mod gpu;
+pub mod graph;
 pub mod resolution;
 pub mod resource;
 #[cfg(feature = "winit")]
 pub mod winit;
 pub mod stage;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub use collections::*;
 pub use error::*;
+pub use frame::*;
 pub use gpu::Gpu;
+pub use gpu::attachment::*;
+pub use gpu::capture::*;
+pub use gpu::options::*;
 pub use gpu::device::*;
 pub use gpu::surface::*;
+pub use graph::*;
 pub use stage::Stage;
+pub use stage::depth::*;
 pub use stage::task::*;
 pub use stage::task::constructor::*;
 pub use stage::task::executor::*;
+pub use stage::task::order::*;
 pub use stage::store::*;
 pub use resolution::*;
 pub use resource::*;
@@ -23,35 +34,98 @@ pub use resource::shaders::*;
 #[cfg(feature = "winit")]
 pub use winit::*;
 
+#[derive(Default)]
 pub struct Renderer<'w> {
     gpu: Option<Gpu<'w>>,
     stages: Stages,
+    clock: Clock,
 }
 
-impl Default for Renderer<'_> {
-    fn default() -> Self {
-        Self {
-            gpu: None,
-            stages: Stages::default(),
-        }
+impl<'w> Renderer<'w> {
+    pub fn draw_frame(&mut self) -> Result<(), Error> {
+        let Some(gpu) = &mut self.gpu else { return Ok(()) };
+        let frame = self.clock.tick(gpu.resolution());
+        gpu.render(&mut self.stages, &frame)
     }
-}
 
-impl<'w> Renderer<'w> {
-    pub fn draw_frame<'e>(&mut self) {
-        let Some(gpu) = &mut self.gpu else { return };
-        gpu.render(&mut self.stages);
+    pub fn capture_frame(&mut self) -> Result<Image, Error> {
+        let Some(gpu) = &mut self.gpu else { return Err(Error::Uninitialized) };
+        let frame = self.clock.tick(gpu.resolution());
+        gpu.render_and_capture(&mut self.stages, &frame)
+    }
+
+    /// Makes tasks update in steps of exactly `step` instead of once per frame.
+    pub fn set_fixed_timestep(&mut self, step: Option<std::time::Duration>) {
+        self.clock.set_fixed_timestep(step);
+    }
+
+    /// Makes every frame advance time by exactly `step` instead of the time it took, so frames
+    /// are reproducible.
+    pub fn set_virtual_timestep(&mut self, step: Option<std::time::Duration>) {
+        self.clock.set_virtual_timestep(step);
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn clock_mut(&mut self) -> &mut Clock {
+        &mut self.clock
     }
 
-    pub fn initialize<T>(&mut self, target: T) -> Result<(), Error>
+    pub fn initialize<T>(&mut self, target: T, options: SurfaceOptions) -> Result<(), Error>
     where T: Into<wgpu::SurfaceTarget<'w>>,
           T: GetResolution<u32> {
-        let gpu = Gpu::new(target.get_resolution(), target)?;
+        let gpu = Gpu::new(target.get_resolution(), target, options)?;
+        self.gpu = Some(gpu);
+        self.stages.initialize(self.gpu.as_mut().unwrap())
+    }
+
+    pub fn initialize_headless(
+        &mut self,
+        resolution: impl Resolution<u32>,
+        format: wgpu::TextureFormat,
+        options: SurfaceOptions,
+    ) -> Result<(), Error> {
+        let gpu = Gpu::headless(resolution, format, options)?;
         self.gpu = Some(gpu);
-        self.stages.initialize(self.gpu.as_mut().unwrap());
+        self.stages.initialize(self.gpu.as_mut().unwrap())
+    }
+
+    /// Resizes the surface and notifies the tasks of every stage.
+    pub fn resize(&mut self, resolution: impl Resolution<u32>) {
+        let Some(gpu) = &mut self.gpu else { return };
+        let resolution = (resolution.get_width(), resolution.get_height());
+        gpu.surface_mut().resize(resolution);
+        self.stages.resize(gpu, resolution);
+    }
+
+    /// Reconfigures the surface with new options and notifies the tasks of every stage.
+    pub fn reconfigure(&mut self, options: SurfaceOptions) -> Result<(), Error> {
+        let Some(gpu) = &mut self.gpu else { return Err(Error::Uninitialized) };
+        gpu.reconfigure(options)?;
+        let target = gpu.surface().target();
+        self.stages.surface_reconfigured(gpu, target);
+        Ok(())
+    }
+
+    /// Sets the number of samples rendered per pixel and notifies the tasks of every stage.
+    pub fn set_sample_count(&mut self, sample_count: u32) -> Result<(), Error> {
+        let Some(gpu) = &mut self.gpu else { return Err(Error::Uninitialized) };
+        gpu.set_sample_count(sample_count)?;
+        let target = gpu.surface().target();
+        self.stages.surface_reconfigured(gpu, target);
         Ok(())
     }
 
+    /// Sets the present mode of the surface and notifies the tasks of every stage.
+    pub fn set_vsync(&mut self, vsync: VSync) {
+        let Some(gpu) = &mut self.gpu else { return };
+        gpu.set_vsync(vsync);
+        let target = gpu.surface().target();
+        self.stages.surface_reconfigured(gpu, target);
+    }
+
     pub fn is_initialized(&self) -> bool {
         self.gpu.is_some()
     }
@@ -81,6 +155,15 @@ impl<'w> Renderer<'w> {
     }
 }
 
+impl Drop for Renderer<'_> {
+    /// Calls [`Task::on_remove`] on the tasks still active in any stage.
+    fn drop(&mut self) {
+        if let Some(gpu) = &mut self.gpu {
+            self.stages.teardown(gpu);
+        }
+    }
+}
+
 macro_rules! reexport {
     ($(mod $name:ident;)+) => {
         $(mod $name;)+
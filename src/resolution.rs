@@ -4,6 +4,16 @@ pub trait Resolution<T>: Copy {
     fn get_height(&self) -> T;
 }
 
+impl<T: Copy> Resolution<T> for (T, T) {
+    fn get_width(&self) -> T {
+        self.0
+    }
+
+    fn get_height(&self) -> T {
+        self.1
+    }
+}
+
 pub trait GetResolution<T> {
     type Resolution: Resolution<T>;
 
@@ -3,6 +3,7 @@ pub mod shaders;
 use std::any::TypeId;
 use crate::collections::TypeMap;
 use crate::Device;
+use crate::render_pipeline::RenderTarget;
 
 pub trait Resource {
     fn create(device: &Device) -> Self;
@@ -10,18 +11,34 @@ pub trait Resource {
 
 pub struct Resources {
     device: Device,
+    surface_target: RenderTarget,
     type_map: TypeMap,
 }
 
 impl Resources {
-    pub fn new(device: Device) -> Self {
+    pub fn new(device: Device, surface_target: RenderTarget) -> Self {
         Self {
             device,
+            surface_target,
             type_map: TypeMap::new(),
         }
     }
 
-    pub fn get<'w, T: Resource + 'static>(&mut self) -> &T {
+    /// The format and sample count of the surface stages render to, which pipelines drawing in
+    /// stages must use.
+    pub fn surface_target(&self) -> RenderTarget {
+        self.surface_target
+    }
+
+    pub fn surface_format(&self) -> wgpu::TextureFormat {
+        self.surface_target.format
+    }
+
+    pub(crate) fn set_surface_target(&mut self, target: RenderTarget) {
+        self.surface_target = target;
+    }
+
+    pub fn get<T: Resource + 'static>(&mut self) -> &T {
         let id = TypeId::of::<T>();
         if !self.type_map.contains_id(id) {
             self.type_map.set_by_id(id, Box::new(T::create(&self.device)));
@@ -30,7 +47,7 @@ impl Resources {
         self.type_map.get().unwrap()
     }
 
-    pub fn get_mut<'w, T: Resource + 'static>(&mut self) -> &mut T {
+    pub fn get_mut<T: Resource + 'static>(&mut self) -> &mut T {
         let id = TypeId::of::<T>();
         if !self.type_map.contains_id(id) {
             self.type_map.set_by_id(id, Box::new(T::create(&self.device)));
@@ -37,12 +37,6 @@ impl std::ops::Index<&'static str> for Shaders {
     }
 }
 
-impl std::ops::IndexMut<&'static str> for Shaders {
-    fn index_mut(&mut self, index: &'static str) -> &mut Self::Output {
-        self.module_map.get_mut(index).unwrap()
-    }
-}
-
 impl Resource for Shaders {
     fn create(device: &Device) -> Self {
         Self {
@@ -0,0 +1,69 @@
+/// Configures the depth/stencil buffer of a [`Stage`](crate::Stage).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthBuffer {
+    pub format: wgpu::TextureFormat,
+    pub clear_depth: f32,
+    pub clear_stencil: u32,
+    /// The comparison pipelines built with [`RenderPipelineBuilder::depth_buffer`](crate::render_pipeline::RenderPipelineBuilder::depth_buffer) use.
+    pub compare: wgpu::CompareFunction,
+    pub depth_store: wgpu::StoreOp,
+    pub stencil_store: wgpu::StoreOp,
+}
+
+impl Default for DepthBuffer {
+    fn default() -> Self {
+        Self {
+            format: wgpu::TextureFormat::Depth32Float,
+            clear_depth: 1.0,
+            clear_stencil: 0,
+            compare: wgpu::CompareFunction::Less,
+            depth_store: wgpu::StoreOp::Store,
+            stencil_store: wgpu::StoreOp::Store,
+        }
+    }
+}
+
+impl DepthBuffer {
+    pub fn new(format: wgpu::TextureFormat) -> Self {
+        Self { format, ..Self::default() }
+    }
+
+    pub fn with_clear_depth(mut self, clear_depth: f32) -> Self {
+        self.clear_depth = clear_depth;
+        self
+    }
+
+    pub fn with_clear_stencil(mut self, clear_stencil: u32) -> Self {
+        self.clear_stencil = clear_stencil;
+        self
+    }
+
+    pub fn with_compare(mut self, compare: wgpu::CompareFunction) -> Self {
+        self.compare = compare;
+        self
+    }
+
+    pub fn with_depth_store(mut self, store: wgpu::StoreOp) -> Self {
+        self.depth_store = store;
+        self
+    }
+
+    pub fn with_stencil_store(mut self, store: wgpu::StoreOp) -> Self {
+        self.stencil_store = store;
+        self
+    }
+
+    pub fn depth_ops(&self) -> Option<wgpu::Operations<f32>> {
+        self.format.has_depth_aspect().then_some(wgpu::Operations {
+            load: wgpu::LoadOp::Clear(self.clear_depth),
+            store: self.depth_store,
+        })
+    }
+
+    pub fn stencil_ops(&self) -> Option<wgpu::Operations<u32>> {
+        self.format.has_stencil_aspect().then_some(wgpu::Operations {
+            load: wgpu::LoadOp::Clear(self.clear_stencil),
+            store: self.stencil_store,
+        })
+    }
+}
@@ -1,31 +1,321 @@
-use crate::{Gpu, Task, TaskConstructor, TaskExecutor};
+use crate::graph::{Node, NodeContext, ResourceId};
+use crate::render_pipeline::RenderTarget;
+use crate::{clear_color, AttachmentId, DepthBuffer, Device, Error, FrameContext, Gpu, LazyComputePass, RenderContext, Resources, Task, TaskConstructor, TaskError, TaskExecutor, TaskId};
 
 pub mod task;
 pub mod store;
 pub mod state;
+pub mod depth;
 
-#[derive(Default)]
 pub struct Stage {
-    clear_color: wgpu::Color,
+    target: ResourceId,
+    reads: Vec<ResourceId>,
+    color_ops: Option<wgpu::Operations<wgpu::Color>>,
+    depth: Option<DepthBuffer>,
+    depth_attachment: Option<AttachmentId>,
     task_executor: TaskExecutor,
 }
 
+impl Default for Stage {
+    fn default() -> Self {
+        Self {
+            target: ResourceId::SURFACE,
+            reads: vec![],
+            color_ops: Some(clear_color(wgpu::Color::default())),
+            depth: None,
+            depth_attachment: None,
+            task_executor: TaskExecutor::default(),
+        }
+    }
+}
+
 impl Stage {
+    /// Renders into a texture of the render graph instead of the surface. The texture must match
+    /// the sample count of the surface and, if the stage has a depth buffer, its resolution.
+    pub fn with_target(mut self, target: ResourceId) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Declares a resource of the render graph this stage's tasks read, so that the stage runs
+    /// after the nodes writing it.
+    pub fn with_read(mut self, resource: ResourceId) -> Self {
+        self.reads.push(resource);
+        self
+    }
+
+    pub fn target(&self) -> ResourceId {
+        self.target
+    }
+
+    pub fn reads(&self) -> &[ResourceId] {
+        &self.reads
+    }
+
+    /// Clears the surface to the given color before this stage renders.
+    pub fn with_clear_color(self, color: wgpu::Color) -> Self {
+        self.with_load_op(wgpu::LoadOp::Clear(color))
+    }
+
+    /// Renders on top of the output of previous stages instead of clearing it.
+    pub fn with_load(self) -> Self {
+        self.with_load_op(wgpu::LoadOp::Load)
+    }
+
+    /// Sets how the color attachment is loaded. Has no effect after
+    /// [`Self::without_color_attachment`].
+    pub fn with_load_op(mut self, load: wgpu::LoadOp<wgpu::Color>) -> Self {
+        if let Some(ops) = &mut self.color_ops {
+            ops.load = load;
+        }
+        self
+    }
+
+    /// Discards the color output after this stage, e.g. when it only writes depth or stencil.
+    pub fn with_discard(self) -> Self {
+        self.with_store_op(wgpu::StoreOp::Discard)
+    }
+
+    /// Sets how the color attachment is stored. Has no effect after
+    /// [`Self::without_color_attachment`].
+    pub fn with_store_op(mut self, store: wgpu::StoreOp) -> Self {
+        if let Some(ops) = &mut self.color_ops {
+            ops.store = store;
+        }
+        self
+    }
+
+    /// Renders this stage without a color attachment, leaving the surface untouched. Without a
+    /// depth buffer either, the stage begins no render pass and its tasks only compute.
+    pub fn without_color_attachment(mut self) -> Self {
+        self.color_ops = None;
+        self
+    }
+
+    pub fn color_ops(&self) -> Option<wgpu::Operations<wgpu::Color>> {
+        self.color_ops
+    }
+
+    pub fn with_depth(mut self, depth: DepthBuffer) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn depth(&self) -> Option<&DepthBuffer> {
+        self.depth.as_ref()
+    }
+
+    pub fn depth_stencil_attachment<'a>(&self, gpu: &'a Gpu) -> Option<wgpu::RenderPassDepthStencilAttachment<'a>> {
+        let (depth, id) = self.depth.as_ref().zip(self.depth_attachment)?;
+        Some(wgpu::RenderPassDepthStencilAttachment {
+            view: gpu.surface().attachment_view(id),
+            depth_ops: depth.depth_ops(),
+            stencil_ops: depth.stencil_ops(),
+        })
+    }
+
     pub fn with_task<T: Task + 'static>(mut self) -> Self {
         self.task_executor.queue_task_constructor(TaskConstructor::new::<T>());
         self
     }
 
-    pub fn render(&self, gpu: &Gpu, render_pass: &mut wgpu::RenderPass<'_>) {
-        self.task_executor.render_active_tasks(gpu.device(), render_pass);
+    /// Adds a task from a constructor, which can also set where the task runs relative to the
+    /// stage's other tasks.
+    pub fn with_task_constructor(mut self, constructor: TaskConstructor) -> Self {
+        self.task_executor.queue_task_constructor(constructor);
+        self
+    }
+
+    /// Adds a task built by a closure, e.g. to give it data other than what [`Task::new`] can
+    /// access.
+    pub fn with_task_fn<T, F>(mut self, f: F) -> Self
+    where
+        T: Task + 'static,
+        F: FnOnce(&Device, &mut Resources) -> T + 'static,
+    {
+        self.task_executor.queue_task_fn(f);
+        self
+    }
+
+    pub fn with_task_try_fn<T, E, F>(mut self, f: F) -> Self
+    where
+        T: Task + 'static,
+        E: Into<TaskError>,
+        F: FnOnce(&Device, &mut Resources) -> Result<T, E> + 'static,
+    {
+        self.task_executor.queue_task_try_fn(f);
+        self
+    }
+
+    pub fn with_task_value<T: Task + 'static>(mut self, task: T) -> Self {
+        self.task_executor.queue_task_value(task);
+        self
+    }
+
+    /// Queues a task that is constructed at the start of the next frame.
+    pub fn spawn_task<T: Task + 'static>(&mut self) -> TaskId {
+        self.task_executor.queue_task::<T>()
+    }
+
+    pub fn spawn_task_constructor(&mut self, constructor: TaskConstructor) -> TaskId {
+        self.task_executor.queue_task_constructor(constructor)
     }
 
-    pub fn initialize(&mut self, gpu: &mut Gpu) {
-        let (device, _, res) = gpu.borrow_mut();
-        self.task_executor.load_pending_tasks(device, res);
+    pub fn spawn_task_fn<T, F>(&mut self, f: F) -> TaskId
+    where
+        T: Task + 'static,
+        F: FnOnce(&Device, &mut Resources) -> T + 'static,
+    {
+        self.task_executor.queue_task_fn(f)
     }
 
-    pub fn update(&mut self, gpu: &Gpu) {
-        self.task_executor.update_active_tasks(gpu.device())
+    pub fn spawn_task_value<T: Task + 'static>(&mut self, task: T) -> TaskId {
+        self.task_executor.queue_task_value(task)
     }
-}
\ No newline at end of file
+
+    pub fn remove_task(&mut self, id: TaskId) -> bool {
+        self.task_executor.remove_task(id)
+    }
+
+    pub fn pause_task(&mut self, id: TaskId) -> bool {
+        self.task_executor.pause_task(id)
+    }
+
+    pub fn resume_task(&mut self, id: TaskId) -> bool {
+        self.task_executor.resume_task(id)
+    }
+
+    pub fn task_executor(&self) -> &TaskExecutor {
+        &self.task_executor
+    }
+
+    pub fn task_executor_mut(&mut self) -> &mut TaskExecutor {
+        &mut self.task_executor
+    }
+
+    pub fn prepare(&mut self, gpu: &Gpu, frame: &FrameContext, encoder: &mut wgpu::CommandEncoder) {
+        let ctx = RenderContext::new(frame, gpu.res(), gpu.scratch());
+        self.task_executor.prepare_active_tasks(gpu.device(), &ctx, encoder);
+    }
+
+    pub fn compute(&mut self, gpu: &Gpu, frame: &FrameContext, compute_pass: &mut LazyComputePass<'_>) {
+        let ctx = RenderContext::new(frame, gpu.res(), gpu.scratch());
+        self.task_executor.compute_active_tasks(gpu.device(), &ctx, compute_pass);
+    }
+
+    pub fn render(&mut self, gpu: &Gpu, frame: &FrameContext, render_pass: &mut wgpu::RenderPass<'_>) {
+        let ctx = RenderContext::new(frame, gpu.res(), gpu.scratch());
+        self.task_executor.render_active_tasks(gpu.device(), &ctx, render_pass);
+    }
+
+    pub fn initialize(&mut self, gpu: &mut Gpu) -> Result<(), Error> {
+        let (device, surface, res) = gpu.borrow_mut();
+        if let Some(depth) = &self.depth && self.depth_attachment.is_none() {
+            self.depth_attachment = Some(surface.create_attachment(depth.format)?);
+        }
+        self.task_executor.load_pending_tasks(device, res)
+    }
+
+    pub fn resize(&mut self, gpu: &Gpu, resolution: (u32, u32)) {
+        self.task_executor.resize_active_tasks(gpu.device(), resolution);
+    }
+
+    pub fn surface_reconfigured(&mut self, gpu: &Gpu, target: RenderTarget) {
+        self.task_executor.reconfigure_active_tasks(gpu.device(), target);
+    }
+
+    /// Removes every task, unloading the active ones immediately.
+    pub fn teardown(&mut self, gpu: &mut Gpu) {
+        let (device, res, _) = gpu.borrow_frame_mut();
+        self.task_executor.remove_all_tasks();
+        self.task_executor.unload_removed_tasks(device, res);
+    }
+
+    /// Unloads tasks removed and constructs tasks spawned since the last frame and updates the active ones.
+    pub fn update(&mut self, gpu: &mut Gpu, frame: &FrameContext) -> Result<(), Error> {
+        let (device, res, scratch) = gpu.borrow_frame_mut();
+        self.task_executor.unload_removed_tasks(device, res);
+        self.task_executor.load_pending_tasks(device, res)?;
+        self.task_executor.update_active_tasks(device, frame, res, scratch);
+        Ok(())
+    }
+}
+
+impl Node for Stage {
+    fn initialize(&mut self, gpu: &mut Gpu) -> Result<(), Error> {
+        Stage::initialize(self, gpu)
+    }
+
+    fn update(&mut self, gpu: &mut Gpu, frame: &FrameContext) -> Result<(), Error> {
+        Stage::update(self, gpu, frame)
+    }
+
+    fn prepare(&mut self, gpu: &Gpu, frame: &FrameContext, encoder: &mut wgpu::CommandEncoder) {
+        Stage::prepare(self, gpu, frame, encoder)
+    }
+
+    fn on_resize(&mut self, gpu: &Gpu, resolution: (u32, u32)) {
+        Stage::resize(self, gpu, resolution)
+    }
+
+    fn on_surface_reconfigured(&mut self, gpu: &Gpu, target: RenderTarget) {
+        Stage::surface_reconfigured(self, gpu, target)
+    }
+
+    fn teardown(&mut self, gpu: &mut Gpu) {
+        Stage::teardown(self, gpu)
+    }
+
+    fn execute(&mut self, ctx: &mut NodeContext<'_>) {
+        let gpu = ctx.gpu();
+        let frame = ctx.frame();
+        self.compute(gpu, frame, &mut LazyComputePass::new(ctx.encoder()));
+
+        // With multisampling, stages draw into the multisampled texture and resolve into the surface.
+        let view = ctx.texture_view(self.target).expect("the graph verifies that stages render to textures");
+        let (view, resolve_target) = match gpu.surface().multisample_view() {
+            Some(multisample) if self.target == ResourceId::SURFACE => (multisample, Some(view)),
+            _ => (view, None),
+        };
+        let color_attachment = self.color_ops.map(|ops| wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops,
+        });
+        let depth_stencil_attachment = self.depth_stencil_attachment(gpu);
+        if color_attachment.is_none() && depth_stencil_attachment.is_none() {
+            return;
+        }
+        let mut render_pass = ctx.encoder().begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: match &color_attachment {
+                Some(_) => std::slice::from_ref(&color_attachment),
+                None => &[],
+            },
+            depth_stencil_attachment,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        self.render(gpu, frame, &mut render_pass);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_ops_do_not_restore_a_removed_attachment() {
+        let stage = Stage::default().without_color_attachment().with_discard().with_load();
+        assert_eq!(stage.color_ops(), None);
+    }
+
+    #[test]
+    fn load_and_store_ops_are_set_independently() {
+        let stage = Stage::default().with_discard().with_load();
+        let ops = stage.color_ops().unwrap();
+        assert_eq!(ops.load, wgpu::LoadOp::Load);
+        assert_eq!(ops.store, wgpu::StoreOp::Discard);
+    }
+}
@@ -1,48 +1,105 @@
-use crate::{Gpu, Stage};
+use crate::graph::{GraphNode, NodeId, RenderGraph};
+use crate::render_pipeline::RenderTarget;
+use crate::{Error, FrameContext, Gpu, Image, PendingCapture, Stage};
 
+/// Identifies a stage added to [`Stages`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StageId(NodeId);
+
+/// The stages of a renderer, which run as nodes of its render graph.
 #[derive(Default)]
 pub struct Stages {
-    vec: Vec<Stage>,
+    graph: RenderGraph,
+    len: usize,
 }
 
 impl Stages {
-    pub fn add_stage(&mut self, stage: Stage) {
-        self.vec.push(stage);
+    pub fn add_stage(&mut self, stage: Stage) -> StageId {
+        let target = stage.target();
+        let reads = stage.reads().to_vec();
+        let mut node = GraphNode::new(format!("stage {}", self.len), stage);
+        for resource in reads {
+            node = node.reads(resource);
+        }
+        let id = self.graph.add_node(node.renders_to(target));
+        self.len += 1;
+        StageId(id)
     }
 
-    pub fn initialize(&mut self, gpu: &mut Gpu) {
-        for stage in &mut self.vec {
-            stage.initialize(gpu);
-        }
+    pub fn get_stage(&self, id: StageId) -> Option<&Stage> {
+        self.graph.get_node(id.0)?.node()
     }
 
-    pub fn update(&mut self, gpu: &Gpu) {
-        for stage in &mut self.vec {
-            stage.update(gpu);
-        }
+    pub fn get_stage_mut(&mut self, id: StageId) -> Option<&mut Stage> {
+        self.graph.get_node_mut(id.0)?.node_mut()
+    }
+
+    pub fn stage(&self, id: StageId) -> &Stage {
+        self.get_stage(id).unwrap()
+    }
+
+    pub fn stage_mut(&mut self, id: StageId) -> &mut Stage {
+        self.get_stage_mut(id).unwrap()
+    }
+
+    pub fn graph(&self) -> &RenderGraph {
+        &self.graph
+    }
+
+    pub fn graph_mut(&mut self) -> &mut RenderGraph {
+        &mut self.graph
+    }
+
+    pub fn initialize(&mut self, gpu: &mut Gpu) -> Result<(), Error> {
+        self.graph.initialize(gpu)
+    }
+
+    pub fn update(&mut self, gpu: &mut Gpu, frame: &FrameContext) -> Result<(), Error> {
+        self.graph.update(gpu, frame)
+    }
+
+    /// Notifies every stage's tasks that the surface was resized.
+    pub fn resize(&mut self, gpu: &Gpu, resolution: (u32, u32)) {
+        self.graph.resize(gpu, resolution);
+    }
+
+    /// Notifies every stage's tasks that the surface was reconfigured.
+    pub fn surface_reconfigured(&mut self, gpu: &Gpu, target: RenderTarget) {
+        self.graph.surface_reconfigured(gpu, target);
+    }
+
+    /// Removes the tasks of every stage, calling [`Task::on_remove`](crate::Task::on_remove).
+    pub fn teardown(&mut self, gpu: &mut Gpu) {
+        self.graph.teardown(gpu);
     }
 
-    pub fn render(&self, gpu: &Gpu) {
-        let (texture, view) = gpu.surface().current_texture_and_view();
+    pub fn render(&mut self, gpu: &Gpu, frame: &FrameContext) -> Result<(), Error> {
+        let Some((texture, view)) = gpu.surface().current_texture_and_view()? else { return Ok(()) };
         let mut encoder = gpu.device().create_encoder();
+        self.encode(gpu, &mut encoder, &view, frame)?;
+        gpu.device().submit_encoder(encoder);
+        texture.present();
+        Ok(())
+    }
 
-        for stage in &self.vec {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: clear_color(stage.clear_color),
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            stage.render(gpu, &mut render_pass);
-        }
+    pub fn capture(&mut self, gpu: &Gpu, frame: &FrameContext) -> Result<Image, Error> {
+        let (texture, view) = gpu.surface().current_texture_and_view()?.ok_or(Error::FrameUnavailable)?;
+        let mut encoder = gpu.device().create_encoder();
+        self.encode(gpu, &mut encoder, &view, frame)?;
+        let capture = PendingCapture::new(gpu.device(), &mut encoder, texture.texture());
         gpu.device().submit_encoder(encoder);
         texture.present();
+        capture?.finish(gpu.device())
+    }
+
+    pub fn encode(
+        &mut self,
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        frame: &FrameContext,
+    ) -> Result<(), Error> {
+        self.graph.execute(gpu, encoder, view, frame)
     }
 }
 
@@ -51,4 +108,4 @@ pub fn clear_color<C>(color: C) -> wgpu::Operations<C> {
         load: wgpu::LoadOp::Clear(color),
         store: wgpu::StoreOp::Store,
     }
-}
\ No newline at end of file
+}
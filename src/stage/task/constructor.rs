@@ -1,22 +1,96 @@
 use crate::resource::Resources;
-use crate::{Device, Task};
+use crate::{Device, Error, Task, TaskError, TaskOrder};
 
+type ConstructFn = dyn FnOnce(&Device, &mut Resources) -> Result<Box<dyn Task>, TaskError>;
+
+/// Builds a task once the stage it belongs to has access to the device.
 pub struct TaskConstructor {
-    function: fn(&Device, &mut Resources) -> Box<dyn Task>,
+    task: &'static str,
+    function: Box<ConstructFn>,
+    order: TaskOrder,
 }
 
 impl TaskConstructor {
-    pub const fn new<T: Task + 'static>() -> Self {
+    pub fn new<T: Task + 'static>() -> Self {
+        Self::try_from_fn(T::try_new)
+    }
+
+    /// Builds the task with a closure, which allows constructing several instances of the same
+    /// task with different data.
+    pub fn from_fn<T, F>(f: F) -> Self
+    where
+        T: Task + 'static,
+        F: FnOnce(&Device, &mut Resources) -> T + 'static,
+    {
+        Self::try_from_fn(move |device, res| Ok::<_, TaskError>(f(device, res)))
+    }
+
+    pub fn try_from_fn<T, E, F>(f: F) -> Self
+    where
+        T: Task + 'static,
+        E: Into<TaskError>,
+        F: FnOnce(&Device, &mut Resources) -> Result<T, E> + 'static,
+    {
         Self {
-            function: construct::<T>,
+            task: std::any::type_name::<T>(),
+            order: TaskOrder::default(),
+            function: Box::new(move |device, res| match f(device, res) {
+                Ok(task) => Ok(Box::new(task) as Box<dyn Task>),
+                Err(e) => Err(e.into()),
+            }),
         }
     }
 
-    pub fn build(&self, device: &Device, res: &mut Resources) -> Box<dyn Task> {
-        (self.function)(device, res)
+    /// Uses a task that has already been built.
+    pub fn from_task<T: Task + 'static>(task: T) -> Self {
+        Self::from_fn(move |_, _| task)
+    }
+
+    pub fn with_order(mut self, order: TaskOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.order.priority = priority;
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.order.label = Some(label.into());
+        self
     }
-}
 
-fn construct<T: Task + 'static>(device: &Device, res: &mut Resources) -> Box<dyn Task> {
-    Box::new(T::new(device, res))
-}
\ No newline at end of file
+    pub fn before(mut self, label: impl Into<String>) -> Self {
+        self.order = self.order.before(label);
+        self
+    }
+
+    pub fn after(mut self, label: impl Into<String>) -> Self {
+        self.order = self.order.after(label);
+        self
+    }
+
+    pub fn order(&self) -> &TaskOrder {
+        &self.order
+    }
+
+    /// The type name of the task this constructs.
+    pub fn task_name(&self) -> &'static str {
+        self.task
+    }
+
+    pub fn build(self, device: &Device, res: &mut Resources) -> Result<Box<dyn Task>, Error> {
+        self.build_with_order(device, res).map(|(task, _)| task)
+    }
+
+    pub(crate) fn build_with_order(
+        self,
+        device: &Device,
+        res: &mut Resources,
+    ) -> Result<(Box<dyn Task>, TaskOrder), Error> {
+        let task = self.task;
+        let built = (self.function)(device, res).map_err(|source| Error::TaskConstruction { task, source })?;
+        Ok((built, self.order))
+    }
+}
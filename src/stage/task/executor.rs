@@ -1,41 +1,224 @@
-use crate::{Device, Resources, Task, TaskConstructor};
+use crate::render_pipeline::RenderTarget;
+use crate::stage::task::order;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::{
+    Device, Error, FrameContext, LazyComputePass, RenderContext, Resources, Task, TaskConstructor, TaskError, TaskOrder, TypeMap,
+    UpdateContext,
+};
 
+/// Identifies a task spawned on a [`TaskExecutor`], whether it is still pending or active. Ids
+/// are unique across executors, so an id passed to another stage matches none of its tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+impl TaskId {
+    fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+struct PendingTask {
+    id: TaskId,
+    constructor: TaskConstructor,
+    paused: bool,
+}
+
+struct ActiveTask {
+    id: TaskId,
+    name: &'static str,
+    task: Box<dyn Task>,
+    order: TaskOrder,
+    paused: bool,
+}
+
+#[derive(Default)]
 pub struct TaskExecutor {
-    constructors: Vec<TaskConstructor>,
-    tasks: Vec<Box<dyn Task>>,
+    constructors: Vec<PendingTask>,
+    tasks: Vec<ActiveTask>,
+    removed: Vec<Box<dyn Task>>,
 }
 
 impl TaskExecutor {
-    pub fn load_pending_tasks(&mut self, device: &Device, res: &mut Resources) {
-        for constructor in &self.constructors {
-            let task = constructor.build(device, res);
-            self.tasks.push(task);
+    /// Constructs the pending tasks in the order they were queued, then sorts the active tasks by
+    /// their [`TaskOrder`]. A task that fails to construct is discarded and its error returned,
+    /// leaving the tasks queued after it pending.
+    pub fn load_pending_tasks(&mut self, device: &Device, res: &mut Resources) -> Result<(), Error> {
+        if self.constructors.is_empty() {
+            return Ok(());
         }
-        self.constructors.clear();
+        let result = self.construct_pending_tasks(device, res);
+        let sorted = self.sort_tasks();
+        result.and(sorted)
     }
 
-    pub fn queue_task_constructor(&mut self, constructor: TaskConstructor) {
-        self.constructors.push(constructor);
+    fn construct_pending_tasks(&mut self, device: &Device, res: &mut Resources) -> Result<(), Error> {
+        let mut pending_tasks = std::mem::take(&mut self.constructors).into_iter();
+        while let Some(pending) = pending_tasks.next() {
+            let name = pending.constructor.task_name();
+            let (task, order) = match pending.constructor.build_with_order(device, res) {
+                Ok(built) => built,
+                Err(e) => {
+                    self.constructors.extend(pending_tasks);
+                    return Err(e);
+                }
+            };
+            self.tasks.push(ActiveTask {
+                id: pending.id,
+                name,
+                task,
+                order,
+                paused: pending.paused,
+            });
+        }
+        Ok(())
     }
 
-    pub fn queue_task<T: Task + 'static>(&mut self) {
+    /// Sorts the active tasks into the order they run, failing if their constraints conflict, in
+    /// which case they run in the order they were added.
+    fn sort_tasks(&mut self) -> Result<(), Error> {
+        self.tasks.sort_by_key(|t| t.id.0);
+        let orders: Vec<_> = self.tasks.iter().map(|t| &t.order).collect();
+        let sorted = order::sort(&orders, |t| self.tasks[t].name)?;
+
+        let mut tasks: Vec<_> = self.tasks.drain(..).map(Some).collect();
+        self.tasks = sorted.into_iter().map(|t| tasks[t].take().unwrap()).collect();
+        Ok(())
+    }
+
+    /// Calls [`Task::on_remove`] on the tasks removed since the last call.
+    pub fn unload_removed_tasks(&mut self, device: &Device, res: &mut Resources) {
+        for mut task in self.removed.drain(..) {
+            task.on_remove(device, res);
+        }
+    }
+
+    /// Queues a task to be constructed at the start of the next frame.
+    pub fn queue_task_constructor(&mut self, constructor: TaskConstructor) -> TaskId {
+        let id = TaskId::next();
+        self.constructors.push(PendingTask { id, constructor, paused: false });
+        id
+    }
+
+    pub fn queue_task<T: Task + 'static>(&mut self) -> TaskId {
         self.queue_task_constructor(TaskConstructor::new::<T>())
     }
 
-    pub fn update_active_tasks(&mut self, device: &Device) {
+    pub fn queue_task_fn<T, F>(&mut self, f: F) -> TaskId
+    where
+        T: Task + 'static,
+        F: FnOnce(&Device, &mut Resources) -> T + 'static,
+    {
+        self.queue_task_constructor(TaskConstructor::from_fn(f))
+    }
+
+    pub fn queue_task_try_fn<T, E, F>(&mut self, f: F) -> TaskId
+    where
+        T: Task + 'static,
+        E: Into<TaskError>,
+        F: FnOnce(&Device, &mut Resources) -> Result<T, E> + 'static,
+    {
+        self.queue_task_constructor(TaskConstructor::try_from_fn(f))
+    }
+
+    pub fn queue_task_value<T: Task + 'static>(&mut self, task: T) -> TaskId {
+        self.queue_task_constructor(TaskConstructor::from_task(task))
+    }
+
+    pub fn update_active_tasks(
+        &mut self,
+        device: &Device,
+        frame: &FrameContext,
+        res: &mut Resources,
+        scratch: &mut TypeMap,
+    ) {
+        for update in frame.updates() {
+            let mut ctx = UpdateContext::new(update, res, scratch);
+            for task in self.tasks.iter_mut().filter(|t| !t.paused) {
+                task.task.update(device, &mut ctx);
+            }
+        }
+    }
+
+    pub fn prepare_active_tasks(&mut self, device: &Device, ctx: &RenderContext, encoder: &mut wgpu::CommandEncoder) {
+        for task in self.tasks.iter_mut().filter(|t| !t.paused) {
+            task.task.prepare(device, ctx, encoder);
+        }
+    }
+
+    pub fn compute_active_tasks(&mut self, device: &Device, ctx: &RenderContext, compute_pass: &mut LazyComputePass<'_>) {
+        for task in self.tasks.iter_mut().filter(|t| !t.paused) {
+            task.task.compute(device, ctx, compute_pass);
+        }
+    }
+
+    pub fn render_active_tasks(&mut self, device: &Device, ctx: &RenderContext, render_pass: &mut wgpu::RenderPass<'_>) {
+        for task in self.tasks.iter_mut().filter(|t| !t.paused) {
+            task.task.render(device, ctx, render_pass);
+        }
+    }
+
+    pub fn resize_active_tasks(&mut self, device: &Device, resolution: (u32, u32)) {
         for task in &mut self.tasks {
-            task.update(device);
+            task.task.on_resize(device, resolution);
+        }
+    }
+
+    pub fn reconfigure_active_tasks(&mut self, device: &Device, target: RenderTarget) {
+        for task in &mut self.tasks {
+            task.task.on_surface_reconfigured(device, target);
+        }
+    }
+
+    /// Removes a pending or active task, returning whether it existed. Active tasks are unloaded
+    /// at the start of the next frame.
+    pub fn remove_task(&mut self, id: TaskId) -> bool {
+        let pending = self.constructors.len();
+        self.constructors.retain(|t| t.id != id);
+        if let Some(index) = self.tasks.iter().position(|t| t.id == id) {
+            self.removed.push(self.tasks.remove(index).task);
+            return true;
         }
+        pending != self.constructors.len()
     }
 
-    pub fn render_active_tasks<'e>(&self, device: &Device, render_pass: &mut wgpu::RenderPass<'e>) {
-        for task in &self.tasks {
-            task.render(device, render_pass);
+    /// Stops updating and rendering a task until it is resumed, returning whether it existed.
+    pub fn pause_task(&mut self, id: TaskId) -> bool {
+        self.set_paused(id, true)
+    }
+
+    pub fn resume_task(&mut self, id: TaskId) -> bool {
+        self.set_paused(id, false)
+    }
+
+    fn set_paused(&mut self, id: TaskId, paused: bool) -> bool {
+        if let Some(task) = self.constructors.iter_mut().find(|t| t.id == id) {
+            task.paused = paused;
+            return true;
         }
+        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+            task.paused = paused;
+            return true;
+        }
+        false
+    }
+
+    pub fn is_paused(&self, id: TaskId) -> Option<bool> {
+        let pending = self.constructors.iter().find(|t| t.id == id).map(|t| t.paused);
+        pending.or_else(|| self.tasks.iter().find(|t| t.id == id).map(|t| t.paused))
+    }
+
+    /// The active tasks in the order they run.
+    pub fn active_tasks(&self) -> impl Iterator<Item = TaskId> + '_ {
+        self.tasks.iter().map(|t| t.id)
+    }
+
+    pub fn contains_task(&self, id: TaskId) -> bool {
+        self.is_paused(id).is_some()
     }
 
     pub fn remove_active_tasks(&mut self) {
-        self.tasks.clear();
+        self.removed.extend(self.tasks.drain(..).map(|t| t.task));
     }
 
     pub fn remove_pending_tasks(&mut self) {
@@ -47,12 +230,3 @@ impl TaskExecutor {
         self.remove_pending_tasks();
     }
 }
-
-impl Default for TaskExecutor {
-    fn default() -> Self {
-        Self {
-            constructors: vec![],
-            tasks: vec![],
-        }
-    }
-}
\ No newline at end of file
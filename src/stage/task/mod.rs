@@ -1,13 +1,79 @@
 pub mod executor;
 pub mod constructor;
+pub mod order;
 
-use crate::Device;
+use crate::{Device, RenderContext, UpdateContext};
+use crate::render_pipeline::RenderTarget;
 use crate::resource::Resources;
 
+/// The error a task's construction may fail with.
+pub type TaskError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Work a [`Stage`](crate::Stage) records each frame.
+///
+/// Tasks whose construction can fail also override [`Task::try_new`], which stages call instead
+/// of [`Task::new`].
 pub trait Task {
-    fn new(device: &Device, res: &mut Resources) -> Self where Self: Sized;
+    fn new(device: &Device, res: &mut Resources) -> Self
+    where
+        Self: Sized;
+
+    /// Constructs the task, reporting failures such as an invalid shader or a missing resource
+    /// through [`Error::TaskConstruction`](crate::Error::TaskConstruction). Defaults to
+    /// [`Task::new`].
+    fn try_new(device: &Device, res: &mut Resources) -> Result<Self, TaskError>
+    where
+        Self: Sized,
+    {
+        Ok(Self::new(device, res))
+    }
+
+    /// Called once per frame, or once per elapsed step with a fixed timestep.
+    fn update(&mut self, device: &Device, ctx: &mut UpdateContext);
+
+    /// Records copies, clears or other commands into the frame's encoder. Runs for every task
+    /// before any stage begins its passes, so the commands land in the same submission.
+    fn prepare(&mut self, _device: &Device, _ctx: &RenderContext, _encoder: &mut wgpu::CommandEncoder) {}
+
+    /// Records compute work, which runs before the render pass of the task's stage. The stage's
+    /// compute pass begins when a task first calls [`LazyComputePass::pass`].
+    fn compute(&mut self, _device: &Device, _ctx: &RenderContext, _compute_pass: &mut LazyComputePass) {}
+
+    fn render(&mut self, device: &Device, ctx: &RenderContext, render_pass: &mut wgpu::RenderPass);
+
+    /// Called after the surface is resized, e.g. to recreate textures that match its size.
+    fn on_resize(&mut self, _device: &Device, _resolution: (u32, u32)) {}
+
+    /// Called after the surface is reconfigured, which may have changed its format or sample
+    /// count, so pipelines drawing to it must be rebuilt.
+    fn on_surface_reconfigured(&mut self, _device: &Device, _target: RenderTarget) {}
+
+    /// Called at the start of the frame after the task was removed, or when the renderer is
+    /// dropped while the task is still active, to release what it owns.
+    fn on_remove(&mut self, _device: &Device, _res: &mut Resources) {}
+}
+
+/// The compute pass of a stage, which only begins once a task records into it, so stages whose
+/// tasks record no compute work encode no pass.
+pub struct LazyComputePass<'a> {
+    encoder: &'a mut wgpu::CommandEncoder,
+    pass: Option<wgpu::ComputePass<'static>>,
+}
+
+impl<'a> LazyComputePass<'a> {
+    pub(crate) fn new(encoder: &'a mut wgpu::CommandEncoder) -> Self {
+        Self { encoder, pass: None }
+    }
 
-    fn update(&mut self, device: &Device);
+    /// Begins the pass if no task has recorded into it yet.
+    pub fn pass(&mut self) -> &mut wgpu::ComputePass<'static> {
+        // The pass is dropped with `self`, before the encoder it borrows can be used again.
+        self.pass.get_or_insert_with(|| {
+            self.encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default()).forget_lifetime()
+        })
+    }
 
-    fn render(&self, device: &Device, render_pass: &mut wgpu::RenderPass);
-}
\ No newline at end of file
+    pub fn is_begun(&self) -> bool {
+        self.pass.is_some()
+    }
+}
@@ -0,0 +1,141 @@
+use crate::Error;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Where a task runs relative to the other tasks of its stage.
+///
+/// Tasks run in ascending priority, and in the order they were added when priorities are equal,
+/// unless a `before` or `after` constraint on another task's label requires otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskOrder {
+    pub priority: i32,
+    pub label: Option<String>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+impl TaskOrder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Runs before every task with the given label.
+    pub fn before(mut self, label: impl Into<String>) -> Self {
+        self.before.push(label.into());
+        self
+    }
+
+    /// Runs after every task with the given label.
+    pub fn after(mut self, label: impl Into<String>) -> Self {
+        self.after.push(label.into());
+        self
+    }
+
+    fn has_label(&self, label: &str) -> bool {
+        self.label.as_deref() == Some(label)
+    }
+}
+
+/// Sorts tasks, given in the order they were added, into the order they run, returning the
+/// indices of the tasks. `name` describes a task in the error returned when constraints conflict.
+pub(crate) fn sort<'a>(
+    orders: &[&TaskOrder],
+    name: impl Fn(usize) -> &'a str,
+) -> Result<Vec<usize>, Error> {
+    let mut edges = vec![vec![]; orders.len()];
+    let mut dependencies = vec![0; orders.len()];
+    for (a, order) in orders.iter().enumerate() {
+        for (b, other) in orders.iter().enumerate() {
+            let a_before_b = order.before.iter().any(|l| other.has_label(l))
+                || other.after.iter().any(|l| order.has_label(l));
+            if a != b && a_before_b {
+                edges[a].push(b);
+                dependencies[b] += 1;
+            }
+        }
+    }
+
+    let mut ready: BinaryHeap<_> = (0..orders.len())
+        .filter(|&t| dependencies[t] == 0)
+        .map(|t| Reverse((orders[t].priority, t)))
+        .collect();
+    let mut sorted = Vec::with_capacity(orders.len());
+    while let Some(Reverse((_, task))) = ready.pop() {
+        sorted.push(task);
+        for &next in &edges[task] {
+            dependencies[next] -= 1;
+            if dependencies[next] == 0 {
+                ready.push(Reverse((orders[next].priority, next)));
+            }
+        }
+    }
+    if let Some(task) = (0..orders.len()).find(|t| !sorted.contains(t)) {
+        let name = orders[task].label.as_deref().unwrap_or_else(|| name(task));
+        return Err(Error::TaskCycle(name.to_string()));
+    }
+    Ok(sorted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort_orders(orders: &[TaskOrder]) -> Result<Vec<usize>, Error> {
+        let names = ["a", "b", "c"];
+        sort(&orders.iter().collect::<Vec<_>>(), |t| names[t])
+    }
+
+    #[test]
+    fn lower_priority_runs_first() {
+        let orders = [
+            TaskOrder::new().with_priority(1),
+            TaskOrder::new(),
+            TaskOrder::new().with_priority(-1),
+        ];
+        assert_eq!(sort_orders(&orders).unwrap(), [2, 1, 0]);
+    }
+
+    #[test]
+    fn equal_priorities_keep_registration_order() {
+        let orders = [TaskOrder::new(), TaskOrder::new(), TaskOrder::new()];
+        assert_eq!(sort_orders(&orders).unwrap(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn after_overrides_priority() {
+        let orders = [
+            TaskOrder::new().with_label("physics"),
+            TaskOrder::new().with_priority(-10).after("physics"),
+            TaskOrder::new().with_priority(-5).before("physics"),
+        ];
+        assert_eq!(sort_orders(&orders).unwrap(), [2, 0, 1]);
+    }
+
+    #[test]
+    fn unknown_labels_are_ignored() {
+        let orders = [
+            TaskOrder::new().after("missing"),
+            TaskOrder::new().with_priority(-1).before("missing"),
+        ];
+        assert_eq!(sort_orders(&orders).unwrap(), [1, 0]);
+    }
+
+    #[test]
+    fn cycle_is_an_error() {
+        let orders = [
+            TaskOrder::new().with_label("a").after("b"),
+            TaskOrder::new().with_label("b").after("a"),
+        ];
+        assert!(matches!(sort_orders(&orders), Err(Error::TaskCycle(name)) if name == "a"));
+    }
+}
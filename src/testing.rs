@@ -0,0 +1,216 @@
+use crate::{Error, Image, Renderer, Stage, SurfaceOptions};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Set this environment variable to overwrite reference images with the rendered output.
+pub const UPDATE_SNAPSHOTS_VAR: &str = "DYNGPU_UPDATE_SNAPSHOTS";
+
+/// Renders stages headlessly and compares the final frame against a reference PNG.
+///
+/// Reference images live in `tests/snapshots/<name>.png` relative to the working directory unless
+/// another directory is given. When a comparison fails, the rendered frame and a diff image are
+/// written next to the reference as `<name>.actual.png` and `<name>.diff.png`.
+///
+/// Frames are drawn with a virtual clock, so tasks animated by the frame timing render the same
+/// image on every run.
+pub struct Snapshot {
+    name: String,
+    directory: PathBuf,
+    resolution: (u32, u32),
+    format: wgpu::TextureFormat,
+    options: SurfaceOptions,
+    frames: u32,
+    timestep: Duration,
+    tolerance: u8,
+    stages: Vec<Stage>,
+}
+
+impl Snapshot {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            directory: PathBuf::from("tests/snapshots"),
+            resolution: (128, 128),
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            options: SurfaceOptions::default(),
+            frames: 1,
+            timestep: Duration::from_secs(1) / 60,
+            tolerance: 2,
+            stages: vec![],
+        }
+    }
+
+    pub fn with_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = directory.into();
+        self
+    }
+
+    pub fn with_resolution(mut self, width: u32, height: u32) -> Self {
+        self.resolution = (width, height);
+        self
+    }
+
+    pub fn with_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_surface_options(mut self, f: impl FnOnce(SurfaceOptions) -> SurfaceOptions) -> Self {
+        self.options = f(self.options);
+        self
+    }
+
+    /// Sets how many frames are drawn; only the last one is compared.
+    pub fn with_frames(mut self, frames: u32) -> Self {
+        self.frames = frames.max(1);
+        self
+    }
+
+    /// Sets how far time advances each frame, which defaults to 1/60 of a second.
+    pub fn with_timestep(mut self, timestep: Duration) -> Self {
+        self.timestep = timestep;
+        self
+    }
+
+    /// Sets the largest per-channel difference that is still considered a match.
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Adds a stage; stages render in the order they are added.
+    pub fn with_stage(mut self, f: impl FnOnce(Stage) -> Stage) -> Self {
+        self.stages.push(f(Stage::default()));
+        self
+    }
+
+    pub fn render(self) -> Result<Image, Error> {
+        let mut renderer = Renderer::default();
+        renderer.set_virtual_timestep(Some(self.timestep));
+        for stage in self.stages {
+            renderer.stages_mut().add_stage(stage);
+        }
+        renderer.initialize_headless(self.resolution, self.format, self.options)?;
+        for _ in 1..self.frames {
+            renderer.draw_frame()?;
+        }
+        renderer.capture_frame()
+    }
+
+    /// Renders the stages and panics if the result does not match the reference image.
+    pub fn assert(self) {
+        let name = self.name.clone();
+        let directory = self.directory.clone();
+        let tolerance = self.tolerance;
+        let actual = self.render()
+            .unwrap_or_else(|e| panic!("failed to render snapshot `{name}`: {e}"));
+
+        let reference_path = directory.join(format!("{name}.png"));
+        if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() {
+            std::fs::create_dir_all(&directory).unwrap();
+            actual.save_png(&reference_path).unwrap();
+            return;
+        }
+
+        let expected = match Image::load_png(&reference_path) {
+            Ok(expected) => expected,
+            Err(e) => panic!(
+                "failed to load reference image {}: {e} (set {UPDATE_SNAPSHOTS_VAR}=1 to create it)",
+                reference_path.display(),
+            ),
+        };
+
+        if let Err(mismatch) = compare(&expected, &actual, tolerance) {
+            actual.save_png(directory.join(format!("{name}.actual.png"))).unwrap();
+            if let Some(diff) = &mismatch.diff {
+                diff.save_png(directory.join(format!("{name}.diff.png"))).unwrap();
+            }
+            panic!("snapshot `{name}` does not match {}: {mismatch}", reference_path.display());
+        }
+    }
+}
+
+/// The result of a failed image comparison.
+#[derive(Debug)]
+pub struct Mismatch {
+    pub differing_pixels: usize,
+    pub max_difference: u8,
+    /// Highlights differing pixels in red over a dimmed copy of the expected image. Absent when
+    /// the images differ in size.
+    pub diff: Option<Image>,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.diff {
+            Some(_) => write!(
+                f,
+                "{} pixels differ, by up to {}",
+                self.differing_pixels, self.max_difference,
+            ),
+            None => write!(f, "image sizes differ"),
+        }
+    }
+}
+
+/// Compares two images, allowing each channel to differ by up to `tolerance`.
+pub fn compare(expected: &Image, actual: &Image, tolerance: u8) -> Result<(), Mismatch> {
+    if expected.width() != actual.width() || expected.height() != actual.height() {
+        return Err(Mismatch { differing_pixels: 0, max_difference: 0, diff: None });
+    }
+
+    let mut differing_pixels = 0;
+    let mut max_difference = 0;
+    let mut diff = Vec::with_capacity(expected.data().len());
+    for (e, a) in expected.data().chunks_exact(4).zip(actual.data().chunks_exact(4)) {
+        let difference = e.iter().zip(a).map(|(e, a)| e.abs_diff(*a)).max().unwrap();
+        max_difference = max_difference.max(difference);
+        if difference > tolerance {
+            differing_pixels += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 12) as u8;
+            diff.extend_from_slice(&[luma, luma, luma, 255]);
+        }
+    }
+
+    if differing_pixels == 0 {
+        return Ok(());
+    }
+
+    Err(Mismatch {
+        differing_pixels,
+        max_difference,
+        diff: Some(Image::new(expected.width(), expected.height(), diff)),
+    })
+}
+
+impl Image {
+    pub fn load_png(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut decoder = png::Decoder::new(std::fs::File::open(path)?);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let data = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+            png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+            png::ColorType::Indexed => unreachable!("indexed images are expanded"),
+        };
+
+        Ok(Self::new(info.width, info.height, data))
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width(), self.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(self.data())?;
+        Ok(())
+    }
+}
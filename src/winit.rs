@@ -1,6 +1,7 @@
 use crate::stage::task::Task;
-use crate::{error, resolution, Renderer, Stage};
+use crate::{error, resolution, Device, Renderer, Resources, Stage, SurfaceOptions};
 use std::sync::Arc;
+use std::time::Duration;
 use winit::application::ApplicationHandler;
 use winit::dpi::{LogicalSize, PhysicalSize};
 use winit::event::WindowEvent;
@@ -33,20 +34,12 @@ impl resolution::GetResolution<u32> for Arc<Window> {
     }
 }
 
+#[derive(Default)]
 pub struct RendererWindow<'w> {
     window: Option<Arc<Window>>,
     render: Renderer<'w>
 }
 
-impl Default for RendererWindow<'_> {
-    fn default() -> Self {
-        Self {
-            window: None,
-            render: Renderer::default()
-        }
-    }
-}
-
 impl<'w> std::ops::Deref for RendererWindow<'w> {
     type Target = Renderer<'w>;
 
@@ -69,6 +62,7 @@ impl<'w> RendererWindow<'w> {
     pub fn initialize(
         &mut self,
         window_attributes: &mut Option<WindowAttributes>,
+        surface_options: SurfaceOptions,
         event_loop: &ActiveEventLoop
     ) -> Result<(), error::Error> {
         let window_attributes = window_attributes.take().unwrap_or_default()
@@ -77,7 +71,7 @@ impl<'w> RendererWindow<'w> {
         let window = event_loop.create_window(window_attributes)?;
 
         self.window = Some(Arc::new(window));
-        self.render.initialize(self.window.as_ref().unwrap().clone())?;
+        self.render.initialize(self.window.as_ref().unwrap().clone(), surface_options)?;
 
         Ok(())
     }
@@ -94,6 +88,8 @@ impl<'w> RendererWindow<'w> {
 pub struct RenderApp<'w> {
     renderer: RendererWindow<'w>,
     window_attributes: Option<WindowAttributes>,
+    surface_options: SurfaceOptions,
+    error: Option<error::Error>,
 }
 
 pub trait App {
@@ -106,8 +102,12 @@ impl App for () {
 
 impl ApplicationHandler for RenderApp<'_> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        if !self.renderer.is_initialized() {
-            self.renderer.initialize(&mut self.window_attributes, event_loop).unwrap();
+        if self.renderer.is_initialized() {
+            return;
+        }
+        if let Err(e) = self.renderer.initialize(&mut self.window_attributes, self.surface_options, event_loop) {
+            self.error = Some(e);
+            event_loop.exit();
         }
     }
 
@@ -115,7 +115,7 @@ impl ApplicationHandler for RenderApp<'_> {
         use WindowEvent::*;
         match event {
             Resized(size) => {
-                self.renderer.gpu_mut().surface_mut().resize(size);
+                self.renderer.resize(size);
             },
             Moved(_) => {}
             CloseRequested | Destroyed => event_loop.exit(),
@@ -142,7 +142,11 @@ impl ApplicationHandler for RenderApp<'_> {
             ThemeChanged(_) => {}
             Occluded(_) => {}
             RedrawRequested => {
-                self.renderer.draw_frame();
+                if let Err(e) = self.renderer.draw_frame() {
+                    self.error = Some(e);
+                    event_loop.exit();
+                    return;
+                }
                 self.renderer.window.as_ref().unwrap().request_redraw();
             }
             _ => {}
@@ -158,6 +162,8 @@ impl Default for RenderApp<'_> {
                 .with_title("")
                 .with_inner_size(LogicalSize::new(720, 480))
             ),
+            surface_options: SurfaceOptions::default(),
+            error: None,
         }
     }
 }
@@ -168,17 +174,42 @@ impl RenderApp<'_> {
         self
     }
 
+    pub fn with_surface_options(mut self, f: impl FnOnce(SurfaceOptions) -> SurfaceOptions) -> Self {
+        self.surface_options = f(self.surface_options);
+        self
+    }
+
+    pub fn with_fixed_timestep(mut self, step: Duration) -> Self {
+        self.renderer.set_fixed_timestep(Some(step));
+        self
+    }
+
+    /// Advances time by exactly `step` every frame, e.g. when recording a video.
+    pub fn with_virtual_timestep(mut self, step: Duration) -> Self {
+        self.renderer.set_virtual_timestep(Some(step));
+        self
+    }
+
     pub fn with_stage_task<T: Task + 'static>(self) -> Self {
         self.with_stage(|s| s.with_task::<T>())
     }
 
+    pub fn with_stage_task_fn<T, F>(self, f: F) -> Self
+    where
+        T: Task + 'static,
+        F: FnOnce(&Device, &mut Resources) -> T + 'static,
+    {
+        self.with_stage(|s| s.with_task_fn(f))
+    }
+
     pub fn with_stage(mut self, f: impl FnOnce(Stage) -> Stage) -> Self {
         self.renderer.stages_mut().add_stage(f(Stage::default()));
         self
     }
 
     pub fn run(mut self) -> Result<(), error::Error> {
-        Ok(EventLoop::new()?.run_app(&mut self)?)
+        EventLoop::new()?.run_app(&mut self)?;
+        self.error.map_or(Ok(()), Err)
     }
 }
 
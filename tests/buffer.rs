@@ -0,0 +1,145 @@
+use dyngpu::{Device, Error, Gpu, SurfaceOptions};
+
+fn device() -> Device {
+    let gpu = Gpu::headless((1u32, 1u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    gpu.device().clone()
+}
+
+#[test]
+fn typed_buffer_writes_in_elements() {
+    let device = device();
+    let buffer = device.build_buffer().contents_slice(&[0u16; 6]).storage_rw().finish_typed::<u16>();
+    assert_eq!(buffer.len(), 6);
+
+    buffer.write(2, &[7, 8]);
+    assert_eq!(device.read_buffer::<u16>(&buffer, ..).unwrap(), [0, 0, 7, 8, 0, 0]);
+}
+
+#[test]
+fn typed_buffer_slices_in_elements() {
+    let device = device();
+    let buffer = device.build_buffer().contents_slice(&[10u32, 20, 30, 40]).readback().finish_typed::<u32>();
+
+    // Flushes the upload of the contents. Mapped ranges must start on a multiple of 8 bytes.
+    device.queue().submit([]);
+    let slice = buffer.slice(2..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.device().poll(wgpu::Maintain::Wait);
+    assert_eq!(bytemuck::cast_slice::<u8, u32>(&slice.get_mapped_range()), [30, 40]);
+}
+
+#[test]
+#[should_panic(expected = "does not start and end on a multiple of 4 bytes")]
+fn typed_buffer_rejects_unaligned_writes() {
+    let device = device();
+    let buffer = device.build_buffer().size(8).copy_dst().finish_typed::<u16>();
+    buffer.write(1, &[7]);
+}
+
+#[test]
+#[should_panic(expected = "overflows a buffer of 4 elements")]
+fn typed_buffer_rejects_overflowing_writes() {
+    let device = device();
+    let buffer = device.build_buffer().size(16).copy_dst().finish_typed::<u32>();
+    buffer.write(u64::MAX, &[1]);
+}
+
+#[test]
+#[should_panic(expected = "is empty or out of bounds of a buffer of 4 elements")]
+fn typed_buffer_rejects_empty_slices() {
+    let device = device();
+    let buffer = device.build_buffer().size(16).vert().finish_typed::<u32>();
+    let _ = buffer.slice(2..2);
+}
+
+#[test]
+#[should_panic(expected = "is empty or out of bounds of a buffer of 4 elements")]
+fn typed_buffer_rejects_overflowing_slices() {
+    let device = device();
+    let buffer = device.build_buffer().size(16).vert().finish_typed::<u32>();
+    let _ = buffer.slice(..=u64::MAX);
+}
+
+#[test]
+fn dynamic_buffer_doubles_when_it_grows() {
+    let device = device();
+    let mut buffer = device.build_buffer().size(16).storage_rw().finish_dynamic::<u32>();
+    assert_eq!((buffer.capacity(), buffer.generation()), (4, 0));
+    assert!(buffer.slice().is_none());
+
+    assert!(!buffer.write(&[1, 2, 3]));
+    assert_eq!((buffer.capacity(), buffer.generation()), (4, 0));
+    assert_eq!(device.read_buffer::<u32>(&buffer, ..12).unwrap(), [1, 2, 3]);
+
+    let data: Vec<u32> = (1..=5).collect();
+    assert!(buffer.write(&data));
+    assert_eq!((buffer.len(), buffer.capacity(), buffer.generation()), (5, 8, 1));
+    assert_eq!(device.read_buffer::<u32>(&buffer, ..20).unwrap(), data);
+
+    let data: Vec<u32> = (1..=20).collect();
+    assert!(buffer.write(&data));
+    assert_eq!((buffer.capacity(), buffer.generation()), (20, 2));
+    assert_eq!(device.read_buffer::<u32>(&buffer, ..).unwrap(), data);
+}
+
+#[test]
+fn dynamic_buffer_starts_with_its_contents() {
+    let device = device();
+    let buffer = device.build_buffer().contents_slice(&[1u32, 2, 3]).storage_rw().finish_dynamic::<u32>();
+    assert_eq!((buffer.len(), buffer.capacity()), (3, 3));
+    assert_eq!(device.read_buffer::<u32>(&buffer, ..12).unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn dynamic_buffer_rejects_partial_elements_and_mapped_init() {
+    let device = device();
+    let partial = device.build_buffer().contents(&[0; 6]).vert().try_finish_dynamic::<u32>();
+    assert!(matches!(partial, Err(Error::UnalignedBufferSize(6, 4))));
+
+    let mapped = device.build_buffer().size(16).vert().mapped_init(|_| {}).try_finish_dynamic::<u32>();
+    assert!(matches!(mapped, Err(Error::MappedDynamicBuffer)));
+}
+
+#[test]
+fn size_only_buffers_are_unmapped() {
+    let device = device();
+    let source = device.build_buffer().contents_slice(&[1u32, 2, 3, 4]).copy_src().finish();
+    let destination = device.build_buffer().size(16).storage_rw().finish();
+
+    let mut encoder = device.create_encoder();
+    encoder.copy_buffer_to_buffer(&source, 0, &destination, 0, 16);
+    device.submit_encoder(encoder);
+    assert_eq!(device.read_buffer::<u32>(&destination, ..).unwrap(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn mapped_init_fills_the_buffer() {
+    let device = device();
+    let buffer = device.build_buffer()
+        .size(8)
+        .copy_src()
+        .mapped_init(|bytes| bytes.copy_from_slice(bytemuck::cast_slice(&[5u32, 6])))
+        .finish();
+    assert_eq!(device.read_buffer::<u32>(&buffer, ..).unwrap(), [5, 6]);
+}
+
+#[test]
+fn invalid_buffers_are_errors() {
+    let device = device();
+    let empty = device.build_buffer().size(0).storage().mapped_init(|_| {}).try_finish();
+    assert!(matches!(empty, Err(Error::EmptyBuffer)));
+
+    let unaligned = device.build_buffer().size(6).vert().mapped_init(|_| {}).try_finish();
+    assert!(matches!(unaligned, Err(Error::UnalignedBufferSize(6, 4))));
+
+    let no_usage = device.build_buffer().size(16).try_finish();
+    assert!(matches!(no_usage, Err(Error::InvalidBufferUsage(_))));
+
+    if !device.device().features().contains(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS) {
+        let read_storage = device.build_buffer().size(16).map_read().storage().try_finish();
+        assert!(matches!(read_storage, Err(Error::InvalidBufferUsage(_))));
+
+        let write_copied_into = device.build_buffer().size(16).map_write().copy_dst().try_finish();
+        assert!(matches!(write_copied_into, Err(Error::InvalidBufferUsage(_))));
+    }
+}
@@ -0,0 +1,112 @@
+use dyngpu::{Device, Gpu, LazyComputePass, RenderContext, Renderer, Resources, Stage, SurfaceOptions, Task, UpdateContext};
+use std::sync::Arc;
+
+const SHADER: &str = "
+@group(0) @binding(0) var<storage, read_write> values: array<u32>;
+
+@compute @workgroup_size(1)
+fn cs(@builtin(global_invocation_id) id: vec3<u32>) {
+    values[id.x] *= 2u;
+}
+";
+
+struct DoubleValues {
+    pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    values: Arc<wgpu::Buffer>,
+    len: u32,
+}
+
+impl DoubleValues {
+    fn with_values(device: &Device, res: &mut Resources, values: &[u32]) -> Self {
+        let buffer = Arc::new(device.build_buffer().contents_slice(values).storage_rw().finish());
+        let (bind_group, layout) = device.build_bind_group()
+            .storage(&buffer, wgpu::ShaderStages::COMPUTE, false)
+            .finish_with_layout();
+        let layout = layout.unwrap();
+
+        let shader = res.create_shader("double", wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.build_compute_pipeline(&shader).bind_group(&layout).finish();
+
+        Self { pipeline, bind_group, values: buffer, len: values.len() as u32 }
+    }
+}
+
+impl Task for DoubleValues {
+    fn new(device: &Device, res: &mut Resources) -> Self {
+        Self::with_values(device, res, &[1])
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {}
+
+    fn compute(&mut self, _: &Device, _: &RenderContext, compute_pass: &mut LazyComputePass) {
+        let compute_pass = compute_pass.pass();
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups(self.len, 1, 1);
+    }
+
+    fn render(&mut self, _: &Device, _: &RenderContext, _: &mut wgpu::RenderPass) {}
+}
+
+#[test]
+fn compute_task_writes_storage_buffer() {
+    let mut renderer = Renderer::default();
+    let stage = renderer.stages_mut().add_stage(Stage::default());
+    renderer.initialize_headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+
+    let device = renderer.gpu().device().clone();
+    let task = DoubleValues::with_values(&device, renderer.gpu_mut().res_mut(), &[1, 2, 3, 4]);
+    let values = task.values.clone();
+    renderer.stages_mut().stage_mut(stage).spawn_task_value(task);
+
+    renderer.draw_frame().unwrap();
+    assert_eq!(device.read_buffer::<u32>(&values, ..).unwrap(), [2, 4, 6, 8]);
+    renderer.draw_frame().unwrap();
+    assert_eq!(device.read_buffer::<u32>(&values, ..).unwrap(), [4, 8, 12, 16]);
+}
+
+const SCALE_SHADER: &str = "
+struct Scale { factor: u32 }
+
+var<push_constant> scale: Scale;
+@group(0) @binding(0) var<storage, read_write> values: array<u32>;
+
+@compute @workgroup_size(1)
+fn cs(@builtin(global_invocation_id) id: vec3<u32>) {
+    values[id.x] *= scale.factor;
+}
+";
+
+#[test]
+fn compute_pipeline_push_constants() {
+    let gpu = Gpu::headless((1u32, 1u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    let device = gpu.device();
+    if !device.supports_push_constants() {
+        return;
+    }
+    let values = device.build_buffer().contents_slice(&[1u32, 2, 3]).storage_rw().finish();
+    let (bind_group, layout) = device.build_bind_group()
+        .storage(&values, wgpu::ShaderStages::COMPUTE, false)
+        .finish_with_layout();
+    let layout = layout.unwrap();
+    let shader = device.device().create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(SCALE_SHADER.into()),
+    });
+    let builder = device.build_compute_pipeline(&shader).bind_group(&layout).push_constants(0..4);
+    let pipeline = builder.finish();
+
+    let mut encoder = device.create_encoder();
+    let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+    compute_pass.set_pipeline(&pipeline);
+    compute_pass.set_bind_group(0, &bind_group, &[]);
+    compute_pass.set_push_constants(0, bytemuck::bytes_of(&3u32));
+    compute_pass.dispatch_workgroups(3, 1, 1);
+    drop(compute_pass);
+    device.submit_encoder(encoder);
+    assert_eq!(device.read_buffer::<u32>(&values, ..).unwrap(), [3, 6, 9]);
+}
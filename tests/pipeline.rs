@@ -0,0 +1,145 @@
+use dyngpu::{Gpu, PendingCapture, SurfaceOptions};
+
+const SHADER: &str = "
+@vertex
+fn vs(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+struct Targets {
+    @location(0) first: vec4<f32>,
+    @location(1) second: vec4<f32>,
+}
+
+@fragment
+fn fs() -> Targets {
+    let color = vec4<f32>(1.0, 0.5, 0.25, 0.5);
+    return Targets(color, color);
+}
+";
+
+fn create_target(gpu: &Gpu) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = gpu.device().device().create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d { width: 4, height: 4, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+fn assert_close(actual: [u8; 4], expected: [u8; 4]) {
+    let close = actual.iter().zip(expected).all(|(a, e)| a.abs_diff(e) <= 1);
+    assert!(close, "expected {expected:?}, got {actual:?}");
+}
+
+#[test]
+fn color_targets_have_their_own_blend_and_write_mask() {
+    let gpu = Gpu::headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    let device = gpu.device();
+    let shader = device.device().create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+    });
+    let pipeline = device.build_pipeline(&shader, wgpu::TextureFormat::Rgba8Unorm)
+        .add_target(wgpu::TextureFormat::Rgba8Unorm)
+        .blend(Some(wgpu::BlendState::ALPHA_BLENDING))
+        .write_mask(wgpu::ColorWrites::GREEN | wgpu::ColorWrites::BLUE)
+        .finish();
+
+    let (first, first_view) = create_target(&gpu);
+    let (second, second_view) = create_target(&gpu);
+    let attachment = |view, clear| Some(wgpu::RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops: dyngpu::clear_color(clear),
+    });
+
+    let mut encoder = device.create_encoder();
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &[
+            attachment(&first_view, wgpu::Color::BLACK),
+            attachment(&second_view, wgpu::Color::BLUE),
+        ],
+        ..Default::default()
+    });
+    render_pass.set_pipeline(&pipeline);
+    render_pass.draw(0..3, 0..1);
+    drop(render_pass);
+    let first = PendingCapture::new(device, &mut encoder, &first).unwrap();
+    let second = PendingCapture::new(device, &mut encoder, &second).unwrap();
+    device.submit_encoder(encoder);
+
+    // The first target replaces every channel.
+    assert_close(first.finish(device).unwrap().pixel(1, 1), [255, 128, 64, 128]);
+    // The second blends over blue, writing only green and blue.
+    assert_close(second.finish(device).unwrap().pixel(1, 1), [0, 64, 159, 255]);
+}
+
+const PUSH_CONSTANT_SHADER: &str = "
+var<push_constant> color: vec4<f32>;
+
+@vertex
+fn vs(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs() -> @location(0) vec4<f32> {
+    return color;
+}
+";
+
+#[test]
+fn render_pipeline_push_constants() {
+    let gpu = Gpu::headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    let device = gpu.device();
+    if !device.supports_push_constants() {
+        return;
+    }
+    let shader = device.device().create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(PUSH_CONSTANT_SHADER.into()),
+    });
+    let pipeline = device.build_pipeline(&shader, wgpu::TextureFormat::Rgba8Unorm)
+        .push_constants(wgpu::ShaderStages::FRAGMENT, 0..16)
+        .finish();
+
+    let (target, view) = create_target(&gpu);
+    let mut encoder = device.create_encoder();
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &view,
+            resolve_target: None,
+            ops: dyngpu::clear_color(wgpu::Color::BLACK),
+        })],
+        ..Default::default()
+    });
+    render_pass.set_pipeline(&pipeline);
+    render_pass.set_push_constants(wgpu::ShaderStages::FRAGMENT, 0, bytemuck::cast_slice(&[0.0f32, 1.0, 0.5, 1.0]));
+    render_pass.draw(0..3, 0..1);
+    drop(render_pass);
+    let capture = PendingCapture::new(device, &mut encoder, &target).unwrap();
+    device.submit_encoder(encoder);
+
+    assert_close(capture.finish(device).unwrap().pixel(1, 1), [0, 255, 128, 255]);
+}
+
+#[test]
+fn gpu_surface_changes_update_the_reported_target() {
+    let mut gpu = Gpu::headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    gpu.set_sample_count(4).unwrap();
+    assert_eq!(gpu.res().surface_target().sample_count, 4);
+
+    gpu.set_vsync(dyngpu::VSync::Off);
+    gpu.reconfigure(*gpu.surface().options()).unwrap();
+    assert_eq!(gpu.surface().options().vsync, dyngpu::VSync::Off);
+    assert_eq!(gpu.res().surface_target(), gpu.surface().target());
+}
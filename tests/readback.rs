@@ -0,0 +1,52 @@
+use dyngpu::{Error, Gpu, SurfaceOptions};
+use std::ops::Bound;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[test]
+fn read_buffer_range() {
+    let gpu = Gpu::headless((1u32, 1u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    let device = gpu.device();
+    let data: Vec<u32> = (1..=10).collect();
+    let buffer = device.build_buffer().contents_slice(&data).storage_rw().finish();
+
+    assert_eq!(device.read_buffer::<u32>(&buffer, ..).unwrap(), data);
+    assert_eq!(device.read_buffer::<u32>(&buffer, 4..12).unwrap(), [2, 3]);
+    assert_eq!(device.read_buffer::<u16>(&buffer, 2..6).unwrap(), [0, 2]);
+    assert!(matches!(device.read_buffer::<u32>(&buffer, 4..44), Err(Error::BufferOutOfRange(_, 40))));
+    assert!(matches!(device.read_buffer::<u32>(&buffer, 0..6), Err(Error::UnalignedBufferSize(6, 4))));
+
+    let past_the_end = (Bound::Excluded(u64::MAX), Bound::Unbounded);
+    assert!(matches!(device.read_buffer::<u32>(&buffer, past_the_end), Err(Error::BufferOutOfRange(_, 40))));
+    assert!(matches!(device.read_buffer::<u32>(&buffer, ..=u64::MAX), Err(Error::BufferOutOfRange(_, 40))));
+}
+
+#[test]
+fn read_buffer_async_completes_while_the_device_is_polled() {
+    let gpu = Gpu::headless((1u32, 1u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    let device = gpu.device();
+    let buffer = device.build_buffer().contents_slice(&[7u32, 8, 9]).storage_rw().finish();
+
+    let done = AtomicBool::new(false);
+    let data = std::thread::scope(|scope| {
+        scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                device.device().poll(wgpu::Maintain::Poll);
+                std::thread::yield_now();
+            }
+        });
+        let data = pollster::block_on(device.read_buffer_async::<u32>(&buffer, 4..));
+        done.store(true, Ordering::Relaxed);
+        data
+    });
+    assert_eq!(data.unwrap(), [8, 9]);
+}
+
+#[test]
+fn read_buffer_requires_copy_src() {
+    let gpu = Gpu::headless((1u32, 1u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    let device = gpu.device();
+    let buffer = device.build_buffer().size(16).storage().finish();
+
+    let result = device.read_buffer::<u32>(&buffer, ..);
+    assert!(matches!(result, Err(Error::InvalidBufferUsage(usage)) if usage == wgpu::BufferUsages::STORAGE));
+}
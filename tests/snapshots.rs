@@ -0,0 +1,348 @@
+use dyngpu::buffer::IndexBuffer;
+use dyngpu::render_pipeline::RenderTarget;
+use dyngpu::testing::{compare, Snapshot};
+use dyngpu::{
+    DepthBuffer, Device, Error, Gpu, GraphNode, Image, Node, NodeContext, RenderContext, Renderer, Resources, ResourceId, Stage,
+    SurfaceOptions, Task, TextureDesc, UpdateContext,
+};
+use std::time::Duration;
+
+#[allow(dead_code)]
+#[path = "../examples/triangle.rs"]
+mod triangle;
+
+#[allow(dead_code)]
+#[path = "../examples/rainbow.rs"]
+mod rainbow;
+
+#[test]
+fn triangle() {
+    Snapshot::new("triangle")
+        .with_stage(|s| s.with_task::<triangle::RenderTriangle>())
+        .assert();
+}
+
+#[test]
+fn rainbow() {
+    Snapshot::new("rainbow")
+        .with_frames(30)
+        .with_stage(|s| s.with_task::<rainbow::RenderRainbow>())
+        .assert();
+}
+
+#[test]
+fn triangle_msaa() {
+    Snapshot::new("triangle_msaa")
+        .with_surface_options(|o| o.with_sample_count(4))
+        .with_stage(|s| s.with_task::<triangle::RenderTriangle>())
+        .assert();
+}
+
+#[test]
+fn layered_stages() {
+    Snapshot::new("layered_stages")
+        .with_stage(|s| s.with_clear_color(wgpu::Color { r: 0.0, g: 0.2, b: 0.4, a: 1.0 }))
+        .with_stage(|s| s.with_load().with_task::<triangle::RenderTriangle>())
+        .assert();
+}
+
+#[test]
+fn stage_without_attachments_leaves_the_surface_untouched() {
+    let image = Snapshot::new("no_attachments")
+        .with_stage(|s| s.with_clear_color(wgpu::Color::RED))
+        .with_stage(|s| s.without_color_attachment().with_task::<triangle::RenderTriangle>())
+        .render()
+        .unwrap();
+    assert_eq!(image.pixel(64, 64), [255, 0, 0, 255]);
+}
+
+#[test]
+fn rainbow_follows_elapsed_time() {
+    let render = |timestep| {
+        Snapshot::new("rainbow")
+            .with_frames(30)
+            .with_timestep(timestep)
+            .with_stage(|s| s.with_task::<rainbow::RenderRainbow>())
+            .render()
+            .unwrap()
+    };
+    let at_60_fps = render(Duration::from_secs(1) / 60);
+    assert_eq!(at_60_fps, render(Duration::from_secs(1) / 60));
+    // The same number of frames spanning twice the time shows a different color.
+    assert_ne!(at_60_fps, render(Duration::from_secs(1) / 30));
+}
+
+struct IndexedTriangle {
+    render_pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: IndexBuffer,
+}
+
+impl Task for IndexedTriangle {
+    fn new(device: &Device, res: &mut Resources) -> Self {
+        let vertices: [[f32; 3]; 4] = [[0.5, -0.5, 0.], [0., 0.5, 0.], [9., 9., 9.], [-0.5, -0.5, 0.]];
+        let vertex_buffer = device.build_buffer().contents_slice(&vertices).vert().finish();
+        let index_buffer = device
+            .build_buffer()
+            .label("indices")
+            .contents_slice(&[1u16, 3, 0])
+            .index(wgpu::IndexFormat::Uint16)
+            .finish_index();
+
+        let shader = res.create_shader("triangle", wgpu::include_wgsl!("../examples/triangle.wgsl"));
+        let render_pipeline = device.build_pipeline(&shader, res.surface_target())
+            .vert_buffer(triangle::VERTEX_BUFFER_LAYOUT)
+            .finish();
+
+        Self { render_pipeline, vertex_buffer, index_buffer }
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {}
+
+    fn render(&mut self, _: &Device, _: &RenderContext, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        self.index_buffer.bind(render_pass);
+        render_pass.draw_indexed(0..self.index_buffer.len(), 0, 0..1);
+    }
+}
+
+#[test]
+fn indexed_triangle() {
+    Snapshot::new("triangle")
+        .with_stage(|s| s.with_task::<IndexedTriangle>())
+        .assert();
+}
+
+const DEPTH_SHADER: &str = "
+struct Vertex {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs(@location(0) position: vec3<f32>, @location(1) color: vec3<f32>) -> Vertex {
+    return Vertex(vec4<f32>(position, 1.0), color);
+}
+
+@fragment
+fn fs(vertex: Vertex) -> @location(0) vec4<f32> {
+    return vec4<f32>(vertex.color, 1.0);
+}
+";
+
+const NEAR: [u8; 4] = [255, 0, 0, 255];
+const FAR: [u8; 4] = [0, 0, 255, 255];
+
+/// A far blue triangle and a near red one that overlap in the middle of the surface.
+struct OverlappingTriangles {
+    render_pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    front_to_back: bool,
+}
+
+impl OverlappingTriangles {
+    fn with_order(device: &Device, res: &mut Resources, depth: &DepthBuffer, front_to_back: bool) -> Self {
+        let (blue, red) = ([0f32, 0., 1.], [1f32, 0., 0.]);
+        let vertices: [[[f32; 3]; 2]; 6] = [
+            [[-0.8, -0.6, 0.75], blue], [[0.4, -0.6, 0.75], blue], [[-0.2, 0.7, 0.75], blue],
+            [[-0.4, -0.7, 0.25], red], [[0.8, -0.7, 0.25], red], [[0.2, 0.6, 0.25], red],
+        ];
+        let vertex_buffer = device.build_buffer().contents_slice(&vertices).vert().finish();
+
+        let shader = res.create_shader("depth", wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(DEPTH_SHADER.into()),
+        });
+        let render_pipeline = device.build_pipeline(&shader, res.surface_target())
+            .vert_buffer(wgpu::VertexBufferLayout {
+                array_stride: size_of::<[[f32; 3]; 2]>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &wgpu::vertex_attr_array!(0 => Float32x3, 1 => Float32x3),
+            })
+            .depth_buffer(depth)
+            .finish();
+
+        Self { render_pipeline, vertex_buffer, front_to_back }
+    }
+
+    fn stage(stage: Stage, front_to_back: bool) -> Stage {
+        let depth = DepthBuffer::default();
+        stage.with_depth(depth).with_task_fn(move |device, res| Self::with_order(device, res, &depth, front_to_back))
+    }
+}
+
+impl Task for OverlappingTriangles {
+    fn new(device: &Device, res: &mut Resources) -> Self {
+        Self::with_order(device, res, &DepthBuffer::default(), false)
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {}
+
+    fn render(&mut self, _: &Device, _: &RenderContext, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        let (first, second) = match self.front_to_back {
+            true => (3..6, 0..3),
+            false => (0..3, 3..6),
+        };
+        render_pass.draw(first, 0..1);
+        render_pass.draw(second, 0..1);
+    }
+}
+
+/// The overlap, a pixel only the far triangle covers and one only the near triangle covers, at
+/// the given resolution.
+fn depth_pixels(image: &Image, (width, height): (u32, u32)) -> [[u8; 4]; 3] {
+    let pixel = |x: f32, y: f32| {
+        image.pixel(((x + 1.) / 2. * width as f32) as u32, ((1. - y) / 2. * height as f32) as u32)
+    };
+    [pixel(0., -0.2), pixel(-0.6, -0.5), pixel(0.6, -0.6)]
+}
+
+#[test]
+fn depth_hides_the_far_triangle_in_either_draw_order() {
+    Snapshot::new("depth")
+        .with_stage(|s| OverlappingTriangles::stage(s, false))
+        .assert();
+    Snapshot::new("depth")
+        .with_stage(|s| OverlappingTriangles::stage(s, true))
+        .assert();
+}
+
+#[test]
+fn depth_attachment_is_multisampled_with_the_surface() {
+    let image = Snapshot::new("depth_msaa")
+        .with_surface_options(|o| o.with_sample_count(4))
+        .with_stage(|s| OverlappingTriangles::stage(s, true))
+        .render()
+        .unwrap();
+    assert_eq!(depth_pixels(&image, (128, 128)), [NEAR, FAR, NEAR]);
+}
+
+#[test]
+fn depth_attachment_resizes_with_the_surface() {
+    let mut renderer = Renderer::default();
+    renderer.stages_mut().add_stage(OverlappingTriangles::stage(Stage::default(), true));
+    renderer.initialize_headless((64u32, 64u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    renderer.draw_frame().unwrap();
+
+    renderer.resize((160u32, 96u32));
+    let image = renderer.capture_frame().unwrap();
+    assert_eq!((image.width(), image.height()), (160, 96));
+    assert_eq!(depth_pixels(&image, (160, 96)), [NEAR, FAR, NEAR]);
+}
+
+const COMPOSITE_SHADER: &str = "
+@group(0) @binding(0) var source: texture_2d<f32>;
+
+@vertex
+fn vs(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureLoad(source, vec2<i32>(position.xy), 0);
+}
+";
+
+/// Copies a texture of the graph, pixel by pixel, into the texture the node writes.
+struct Composite {
+    source: ResourceId,
+    target: RenderTarget,
+    pipeline: Option<(wgpu::RenderPipeline, wgpu::BindGroupLayout)>,
+}
+
+impl Composite {
+    fn node(name: &str, source: ResourceId, target: ResourceId, format: wgpu::TextureFormat) -> GraphNode {
+        let composite = Self { source, target: format.into(), pipeline: None };
+        GraphNode::new(name, composite).reads(source).writes(target)
+    }
+}
+
+impl Node for Composite {
+    fn initialize(&mut self, gpu: &mut Gpu) -> Result<(), Error> {
+        let device = gpu.device();
+        let layout = device.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let shader = gpu.res_mut().create_shader("composite", wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(COMPOSITE_SHADER.into()),
+        });
+        let pipeline = gpu.device().build_pipeline(&shader, self.target).bind_group(&layout).finish();
+        self.pipeline = Some((pipeline, layout));
+        Ok(())
+    }
+
+    fn execute(&mut self, ctx: &mut NodeContext<'_>) {
+        let (pipeline, layout) = self.pipeline.as_ref().unwrap();
+        let source = ctx.texture_view(self.source).unwrap();
+        let bind_group = ctx.gpu().device().device().create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(source) }],
+        });
+        let mut render_pass = ctx.begin_render_pass();
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[test]
+fn transient_textures_composite_onto_the_surface() {
+    let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+    let mut renderer = Renderer::default();
+    let graph = renderer.stages_mut().graph_mut();
+    let [first, second, third] = [(); 3].map(|_| graph.create_texture(TextureDesc::new(format)));
+    // The triangle passes through three transient textures, the first and last of which alias.
+    graph.add_node(Composite::node("second", first, second, format));
+    graph.add_node(Composite::node("third", second, third, format));
+    graph.add_node(Composite::node("surface", third, ResourceId::SURFACE, format));
+    renderer.stages_mut().add_stage(Stage::default().with_target(first).with_task::<triangle::RenderTriangle>());
+    renderer.initialize_headless((128u32, 128u32), format, SurfaceOptions::default()).unwrap();
+
+    // Copying texels is lossless, so the surface matches the triangle drawn directly.
+    let image = renderer.capture_frame().unwrap();
+    let expected = Image::load_png("tests/snapshots/triangle.png").unwrap();
+    if let Err(mismatch) = compare(&expected, &image, 2) {
+        panic!("composited triangle does not match tests/snapshots/triangle.png: {mismatch}");
+    }
+    assert_eq!(renderer.stages().graph().allocated_resources(), 2);
+
+    renderer.resize((64u32, 48u32));
+    let image = renderer.capture_frame().unwrap();
+    assert_eq!((image.width(), image.height()), (64, 48));
+    assert_eq!(image.pixel(32, 24), [255, 0, 255, 255]);
+    assert_eq!(image.pixel(2, 2), expected.pixel(2, 2));
+    assert_eq!(renderer.stages().graph().allocated_resources(), 2);
+}
+
+#[test]
+fn bgra_surfaces_capture_as_rgba() {
+    Snapshot::new("layered_stages")
+        .with_format(wgpu::TextureFormat::Bgra8UnormSrgb)
+        .with_stage(|s| s.with_clear_color(wgpu::Color { r: 0.0, g: 0.2, b: 0.4, a: 1.0 }))
+        .with_stage(|s| s.with_load().with_task::<triangle::RenderTriangle>())
+        .assert();
+
+    let image = Snapshot::new("red")
+        .with_format(wgpu::TextureFormat::Bgra8UnormSrgb)
+        .with_stage(|s| s.with_clear_color(wgpu::Color::RED))
+        .render()
+        .unwrap();
+    assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+}
@@ -0,0 +1,357 @@
+use dyngpu::render_pipeline::RenderTarget;
+use dyngpu::{Device, Error, RenderContext, Renderer, Resources, Stage, StageId, SurfaceOptions, Task, TaskConstructor, TaskError, TaskId, UpdateContext, VSync};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+struct MissingShader;
+
+impl Task for MissingShader {
+    fn new(device: &Device, res: &mut Resources) -> Self {
+        Self::try_new(device, res).unwrap()
+    }
+
+    fn try_new(_: &Device, _: &mut Resources) -> Result<Self, TaskError> {
+        Err("shader `missing` is not loaded".into())
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {}
+
+    fn render(&mut self, _: &Device, _: &RenderContext, _: &mut wgpu::RenderPass) {}
+}
+
+#[test]
+fn failed_construction_names_the_task() {
+    let mut renderer = Renderer::default();
+    renderer.stages_mut().add_stage(Stage::default().with_task::<MissingShader>());
+    let result = renderer.initialize_headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default());
+
+    match result {
+        Err(Error::TaskConstruction { task, source }) => {
+            assert_eq!(task, std::any::type_name::<MissingShader>());
+            assert_eq!(source.to_string(), "shader `missing` is not loaded");
+        }
+        other => panic!("expected a construction error, got {other:?}"),
+    }
+}
+
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl Recorder {
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.0.lock().unwrap())
+    }
+
+    fn record(&self, event: String) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+impl Task for Recorder {
+    fn new(_: &Device, _: &mut Resources) -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {}
+
+    fn render(&mut self, _: &Device, _: &RenderContext, _: &mut wgpu::RenderPass) {}
+
+    fn on_resize(&mut self, _: &Device, (width, height): (u32, u32)) {
+        self.record(format!("resize {width}x{height}"));
+    }
+
+    fn on_surface_reconfigured(&mut self, _: &Device, target: RenderTarget) {
+        self.record(format!("reconfigure {}", target.sample_count));
+    }
+
+    fn on_remove(&mut self, _: &Device, _: &mut Resources) {
+        self.record("remove".to_string());
+    }
+}
+
+fn recording_renderer() -> (Renderer<'static>, Recorder, StageId, TaskId) {
+    let recorder = Recorder::default();
+    let mut renderer = Renderer::default();
+    let stage = renderer.stages_mut().add_stage(Stage::default());
+    let task = renderer.stages_mut().stage_mut(stage).spawn_task_value(recorder.clone());
+    renderer.initialize_headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+    (renderer, recorder, stage, task)
+}
+
+#[test]
+fn surface_changes_notify_tasks() {
+    let (mut renderer, recorder, _, _) = recording_renderer();
+
+    renderer.resize((8u32, 6u32));
+    renderer.set_sample_count(4).unwrap();
+    renderer.set_vsync(VSync::Off);
+    renderer.reconfigure(SurfaceOptions::default()).unwrap();
+    assert_eq!(recorder.take(), ["resize 8x6", "reconfigure 4", "reconfigure 4", "reconfigure 1"]);
+}
+
+#[test]
+fn removed_tasks_are_unloaded_on_the_next_frame() {
+    let (mut renderer, recorder, stage, task) = recording_renderer();
+
+    assert!(renderer.stages_mut().stage_mut(stage).remove_task(task));
+    assert!(recorder.take().is_empty());
+    renderer.draw_frame().unwrap();
+    assert_eq!(recorder.take(), ["remove"]);
+
+    drop(renderer);
+    assert!(recorder.take().is_empty());
+}
+
+#[test]
+fn dropping_the_renderer_unloads_active_tasks() {
+    let (renderer, recorder, _, _) = recording_renderer();
+
+    drop(renderer);
+    assert_eq!(recorder.take(), ["remove"]);
+}
+
+#[derive(Clone, Default)]
+struct Counter(Arc<AtomicU32>);
+
+impl Counter {
+    fn get(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Task for Counter {
+    fn new(_: &Device, _: &mut Resources) -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&mut self, _: &Device, _: &RenderContext, _: &mut wgpu::RenderPass) {}
+}
+
+#[test]
+fn tasks_spawned_after_initialization_run_until_paused_or_removed() {
+    let mut renderer = Renderer::default();
+    let stage = renderer.stages_mut().add_stage(Stage::default());
+    renderer.initialize_headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+
+    let counter = Counter::default();
+    let task = renderer.stages_mut().stage_mut(stage).spawn_task_value(counter.clone());
+    assert_eq!(renderer.stages().stage(stage).task_executor().active_tasks().count(), 0);
+    renderer.draw_frame().unwrap();
+    assert_eq!(counter.get(), 1);
+    assert!(renderer.stages().stage(stage).task_executor().active_tasks().eq([task]));
+
+    assert!(renderer.stages_mut().stage_mut(stage).pause_task(task));
+    renderer.draw_frame().unwrap();
+    assert_eq!(counter.get(), 1);
+
+    assert!(renderer.stages_mut().stage_mut(stage).resume_task(task));
+    renderer.draw_frame().unwrap();
+    assert_eq!(counter.get(), 2);
+
+    assert!(renderer.stages_mut().stage_mut(stage).remove_task(task));
+    renderer.draw_frame().unwrap();
+    assert_eq!(counter.get(), 2);
+    assert!(!renderer.stages().stage(stage).task_executor().contains_task(task));
+    assert!(!renderer.stages_mut().stage_mut(stage).remove_task(task));
+}
+
+struct Frame(u32);
+
+#[derive(Default)]
+struct FrameWriter(u32);
+
+impl Task for FrameWriter {
+    fn new(_: &Device, _: &mut Resources) -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, _: &Device, ctx: &mut UpdateContext) {
+        self.0 += 1;
+        ctx.scratch_mut().set(Frame(self.0));
+    }
+
+    fn render(&mut self, _: &Device, _: &RenderContext, _: &mut wgpu::RenderPass) {}
+}
+
+#[derive(Clone, Default)]
+struct FrameReader(Arc<Mutex<Vec<Option<u32>>>>);
+
+impl Task for FrameReader {
+    fn new(_: &Device, _: &mut Resources) -> Self {
+        Self::default()
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {}
+
+    fn render(&mut self, _: &Device, ctx: &RenderContext, _: &mut wgpu::RenderPass) {
+        self.0.lock().unwrap().push(ctx.scratch().get::<Frame>().map(|frame| frame.0));
+    }
+}
+
+#[test]
+fn scratch_passes_values_from_update_to_render_until_the_next_update() {
+    let reader = FrameReader::default();
+    let mut renderer = Renderer::default();
+    let writer_stage = renderer.stages_mut().add_stage(Stage::default());
+    let writer = renderer.stages_mut().stage_mut(writer_stage).spawn_task::<FrameWriter>();
+    renderer.stages_mut().add_stage(Stage::default().with_load().with_task_value(reader.clone()));
+    renderer.initialize_headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+
+    renderer.draw_frame().unwrap();
+    renderer.draw_frame().unwrap();
+    renderer.stages_mut().stage_mut(writer_stage).pause_task(writer);
+    renderer.draw_frame().unwrap();
+    assert_eq!(*reader.0.lock().unwrap(), [Some(1), Some(2), None]);
+
+    // Frames between two fixed steps run no updates and render with the last update's values.
+    renderer.stages_mut().stage_mut(writer_stage).resume_task(writer);
+    renderer.set_virtual_timestep(Some(Duration::from_millis(10)));
+    renderer.set_fixed_timestep(Some(Duration::from_millis(20)));
+    for _ in 0..4 {
+        renderer.draw_frame().unwrap();
+    }
+    assert_eq!(reader.0.lock().unwrap()[3..], [None, Some(3), Some(3), Some(4)]);
+}
+
+#[test]
+fn task_ids_are_unique_across_stages() {
+    let mut renderer = Renderer::default();
+    let first = renderer.stages_mut().add_stage(Stage::default());
+    let second = renderer.stages_mut().add_stage(Stage::default());
+    let task = renderer.stages_mut().stage_mut(first).spawn_task::<Counter>();
+    let other = renderer.stages_mut().stage_mut(second).spawn_task::<Counter>();
+    assert_ne!(task, other);
+
+    assert!(!renderer.stages_mut().stage_mut(second).pause_task(task));
+    assert!(!renderer.stages_mut().stage_mut(second).remove_task(task));
+    assert!(renderer.stages().stage(second).task_executor().contains_task(other));
+    assert!(renderer.stages_mut().stage_mut(first).remove_task(task));
+}
+
+const FILL_SHADER: &str = "
+@group(0) @binding(0) var<uniform> color: vec4<f32>;
+
+@vertex
+fn vs(@builtin(vertex_index) i: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((i << 1u) & 2u), f32(i & 2u));
+    return vec4<f32>(uv * 4.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs() -> @location(0) vec4<f32> {
+    return color;
+}
+";
+
+/// Fills the surface with the color in a uniform buffer.
+struct Fill {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Fill {
+    fn with_color(device: &Device, res: &mut Resources, color: &wgpu::Buffer) -> Self {
+        let (bind_group, layout) = device.build_bind_group().uniform(color, wgpu::ShaderStages::FRAGMENT).finish_with_layout();
+        let layout = layout.unwrap();
+        let shader = res.create_shader("fill", wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(FILL_SHADER.into()),
+        });
+        let pipeline = device.build_pipeline(&shader, res.surface_target()).bind_group(&layout).finish();
+        Self { pipeline, bind_group }
+    }
+}
+
+impl Task for Fill {
+    fn new(_: &Device, _: &mut Resources) -> Self {
+        unimplemented!("constructed with Fill::with_color")
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {}
+
+    fn render(&mut self, _: &Device, _: &RenderContext, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Copies a color into a buffer while preparing the frame.
+struct CopyColor {
+    source: wgpu::Buffer,
+    destination: Arc<wgpu::Buffer>,
+}
+
+impl Task for CopyColor {
+    fn new(_: &Device, _: &mut Resources) -> Self {
+        unimplemented!("constructed with a destination buffer")
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {}
+
+    fn prepare(&mut self, _: &Device, _: &RenderContext, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_buffer_to_buffer(&self.source, 0, &self.destination, 0, self.source.size());
+    }
+
+    fn render(&mut self, _: &Device, _: &RenderContext, _: &mut wgpu::RenderPass) {}
+}
+
+#[test]
+fn prepare_runs_before_the_passes_of_earlier_stages() {
+    let mut renderer = Renderer::default();
+    let fill_stage = renderer.stages_mut().add_stage(Stage::default());
+    let copy_stage = renderer.stages_mut().add_stage(Stage::default().with_load());
+    renderer.initialize_headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+
+    let device = renderer.gpu().device().clone();
+    let red = [1f32, 0., 0., 1.];
+    let green = [0f32, 1., 0., 1.];
+    let color = Arc::new(device.build_buffer().contents_slice(&red).uniform().copy_dst().finish());
+    let fill = Fill::with_color(&device, renderer.gpu_mut().res_mut(), &color);
+    let source = device.build_buffer().contents_slice(&green).copy_src().finish();
+    renderer.stages_mut().stage_mut(fill_stage).spawn_task_value(fill);
+    renderer.stages_mut().stage_mut(copy_stage).spawn_task_value(CopyColor { source, destination: color });
+
+    let image = renderer.capture_frame().unwrap();
+    assert_eq!(image.pixel(1, 1), [0, 255, 0, 255]);
+}
+
+/// Records its tag every update.
+struct Tagged {
+    tag: &'static str,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Task for Tagged {
+    fn new(_: &Device, _: &mut Resources) -> Self {
+        Self { tag: "default", log: Arc::default() }
+    }
+
+    fn update(&mut self, _: &Device, _: &mut UpdateContext) {
+        self.log.lock().unwrap().push(self.tag);
+    }
+
+    fn render(&mut self, _: &Device, _: &RenderContext, _: &mut wgpu::RenderPass) {}
+}
+
+#[test]
+fn closures_construct_instances_of_one_task_with_different_data() {
+    let log = Arc::new(Mutex::new(vec![]));
+    let (first, second) = (log.clone(), log.clone());
+    let stage = Stage::default()
+        .with_task_fn(move |_, _| Tagged { tag: "first", log: first })
+        .with_task_constructor(TaskConstructor::from_fn(move |_, _| Tagged { tag: "second", log: second }));
+    let mut renderer = Renderer::default();
+    let stage = renderer.stages_mut().add_stage(stage);
+    renderer.initialize_headless((4u32, 4u32), wgpu::TextureFormat::Rgba8UnormSrgb, SurfaceOptions::default()).unwrap();
+
+    renderer.draw_frame().unwrap();
+    renderer.draw_frame().unwrap();
+    assert_eq!(*log.lock().unwrap(), ["first", "second", "first", "second"]);
+    assert_eq!(renderer.stages().stage(stage).task_executor().active_tasks().count(), 2);
+}